@@ -0,0 +1,112 @@
+use askama::Template;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use qtpl_bench::{generic, Comment, Team};
+use std::io::Result;
+
+#[derive(Template)]
+#[template(
+    ext = "html",
+    source = "<table>{% for row in table %}<tr>{% for col in row %}<td>{{ col }}</td>{% endfor %}</tr>{% endfor %}</table>"
+)]
+struct BigTable<'a> {
+    table: &'a [Vec<usize>],
+}
+
+#[derive(Template)]
+#[template(
+    ext = "html",
+    source = r#"<html><head><title>{{ year }}</title></head><body><h1>CSL {{ year }}</h1><ul>{% for team in teams %}<li class="{% if loop.first %}champion{% endif %}"><b>{{ team.name }}</b>: {{ team.score }}</li>{% endfor %}</ul></body></html>"#
+)]
+struct Teams<'a> {
+    year: u16,
+    teams: &'a [Team],
+}
+
+#[derive(Template)]
+#[template(
+    ext = "html",
+    source = r#"<section>{% for c in comments %}<article title="{{ c.author }}"><p>{{ c.body }}</p></article>{% endfor %}</section>"#
+)]
+struct Comments<'a> {
+    comments: &'a [Comment],
+}
+
+// Benchmarks a workload rendered by the template function, which writes to a
+// `&mut dyn Write`, the same templates in a function generic over the writer,
+// and askama. The buffers are reused, so allocation isn't measured.
+fn bench<D, G, A>(c: &mut Criterion, name: &str, dyn_fn: D, generic_fn: G, askama: &A)
+where
+    D: Fn(&mut Vec<u8>) -> Result<()>,
+    G: Fn(&mut Vec<u8>) -> Result<()>,
+    A: Template,
+{
+    let mut w = vec![];
+    dyn_fn(&mut w).unwrap();
+    let expected = w.clone();
+    w.clear();
+    generic_fn(&mut w).unwrap();
+    assert_eq!(w, expected, "{} differs between the writer paths", name);
+
+    let mut group = c.benchmark_group(name);
+    group.bench_function("qtpl", |b| {
+        b.iter(|| {
+            w.clear();
+            dyn_fn(black_box(&mut w)).unwrap();
+        })
+    });
+    group.bench_function("qtpl_generic", |b| {
+        b.iter(|| {
+            w.clear();
+            generic_fn(black_box(&mut w)).unwrap();
+        })
+    });
+    let mut s = String::with_capacity(expected.len());
+    group.bench_function("askama", |b| {
+        b.iter(|| {
+            s.clear();
+            askama.render_into(black_box(&mut s)).unwrap();
+        })
+    });
+    group.finish();
+}
+
+fn big_table(c: &mut Criterion) {
+    let table = qtpl_bench::table_data(100);
+    bench(
+        c,
+        "big_table",
+        |w| qtpl_bench::big_table(w, &table),
+        |w| generic::big_table(w, &table),
+        &BigTable { table: &table },
+    );
+}
+
+fn teams(c: &mut Criterion) {
+    let (year, teams) = qtpl_bench::teams_data();
+    bench(
+        c,
+        "teams",
+        |w| qtpl_bench::teams(w, year, &teams),
+        |w| generic::teams(w, year, &teams),
+        &Teams {
+            year,
+            teams: &teams,
+        },
+    );
+}
+
+fn comments(c: &mut Criterion) {
+    let comments = qtpl_bench::comments_data(50);
+    bench(
+        c,
+        "comments",
+        |w| qtpl_bench::comments(w, &comments),
+        |w| generic::comments(w, &comments),
+        &Comments {
+            comments: &comments,
+        },
+    );
+}
+
+criterion_group!(benches, big_table, teams, comments);
+criterion_main!(benches);
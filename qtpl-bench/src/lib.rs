@@ -0,0 +1,206 @@
+//! Workloads for the benchmarks. Each is a template function, which writes to a
+//! `&mut dyn Write`, and also in [`generic`](generic/index.html) as a function
+//! generic over the writer, using the same templates.
+
+use qtpl::{tpl, tplfn};
+
+/// A square table of numbers, rendered by [`big_table`](fn.big_table.html).
+pub fn table_data(size: usize) -> Vec<Vec<usize>> {
+    (0..size).map(|_| (0..size).collect()).collect()
+}
+
+/// A team and its score, rendered by [`teams`](fn.teams.html).
+pub struct Team {
+    pub name: String,
+    pub score: u8,
+}
+
+/// The year and teams rendered by [`teams`](fn.teams.html).
+pub fn teams_data() -> (u16, Vec<Team>) {
+    let teams = [
+        ("Jiangsu", 43),
+        ("Beijing", 27),
+        ("Guangzhou", 22),
+        ("Shandong", 12),
+    ];
+    let teams = teams
+        .iter()
+        .map(|&(name, score)| Team {
+            name: name.to_owned(),
+            score,
+        })
+        .collect();
+    (2015, teams)
+}
+
+/// A comment written by a user, rendered by [`comments`](fn.comments.html).
+pub struct Comment {
+    pub author: String,
+    pub body: String,
+}
+
+/// Comments where most of the text needs escaping.
+pub fn comments_data(count: usize) -> Vec<Comment> {
+    (0..count)
+        .map(|i| Comment {
+            author: format!("<user {}> & \"friends\"", i),
+            body: r#"<script>alert("Tom & Jerry's <3")</script> / 'quoted' & <b>bold</b>"#
+                .repeat(4),
+        })
+        .collect()
+}
+
+#[tplfn]
+pub fn big_table(table: &[Vec<usize>]) {
+    tpl! { <table>{!t big_table_rows(table)}</table> }
+}
+
+#[tplfn]
+fn big_table_rows(table: &[Vec<usize>]) {
+    for row in table {
+        big_table_row(w, row)?;
+    }
+    Ok(())
+}
+
+#[tplfn]
+fn big_table_row(row: &[usize]) {
+    tpl! { <tr>{!t big_table_cells(row)}</tr> }
+}
+
+#[tplfn]
+fn big_table_cells(row: &[usize]) {
+    for col in row {
+        big_table_cell(w, *col)?;
+    }
+    Ok(())
+}
+
+#[tplfn]
+fn big_table_cell(col: usize) {
+    tpl! { <td>{&col.to_string()}</td> }
+}
+
+#[tplfn]
+pub fn teams(year: u16, teams: &[Team]) {
+    tpl! {
+        <html>
+            <head><title>{&year.to_string()}</title></head>
+            <body>
+                <h1>CSL {&year.to_string()}</h1>
+                <ul>{!t team_list(teams)}</ul>
+            </body>
+        </html>
+    }
+}
+
+#[tplfn]
+fn team_list(teams: &[Team]) {
+    for (i, team) in teams.iter().enumerate() {
+        team_item(w, team, i == 0)?;
+    }
+    Ok(())
+}
+
+#[tplfn]
+fn team_item(team: &Team, champion: bool) {
+    tpl! {
+        <li class={if champion { "champion" } else { "" }}>
+            <b>{team.name}</b>: {&team.score.to_string()}
+        </li>
+    }
+}
+
+#[tplfn]
+pub fn comments(comments: &[Comment]) {
+    tpl! { <section>{!t comment_list(comments)}</section> }
+}
+
+#[tplfn]
+fn comment_list(comments: &[Comment]) {
+    for c in comments {
+        comment(w, c)?;
+    }
+    Ok(())
+}
+
+#[tplfn]
+fn comment(c: &Comment) {
+    tpl! { <article title={c.author}><p>{c.body}</p></article> }
+}
+
+/// The same workloads as functions generic over the writer, so the static
+/// parts of the templates are written without dynamic dispatch.
+pub mod generic {
+    use super::{Comment, Team};
+    use qtpl::tpl;
+    use std::io::{Result, Write};
+
+    pub fn big_table<W: Write>(w: &mut W, table: &[Vec<usize>]) -> Result<()> {
+        tpl! { <table>{!t big_table_rows(table)}</table> }
+    }
+
+    fn big_table_rows<W: Write>(w: &mut W, table: &[Vec<usize>]) -> Result<()> {
+        for row in table {
+            big_table_row(w, row)?;
+        }
+        Ok(())
+    }
+
+    fn big_table_row<W: Write>(w: &mut W, row: &[usize]) -> Result<()> {
+        tpl! { <tr>{!t big_table_cells(row)}</tr> }
+    }
+
+    fn big_table_cells<W: Write>(w: &mut W, row: &[usize]) -> Result<()> {
+        for col in row {
+            big_table_cell(w, *col)?;
+        }
+        Ok(())
+    }
+
+    fn big_table_cell<W: Write>(w: &mut W, col: usize) -> Result<()> {
+        tpl! { <td>{&col.to_string()}</td> }
+    }
+
+    pub fn teams<W: Write>(w: &mut W, year: u16, teams: &[Team]) -> Result<()> {
+        tpl! {
+            <html>
+                <head><title>{&year.to_string()}</title></head>
+                <body>
+                    <h1>CSL {&year.to_string()}</h1>
+                    <ul>{!t team_list(teams)}</ul>
+                </body>
+            </html>
+        }
+    }
+
+    fn team_list<W: Write>(w: &mut W, teams: &[Team]) -> Result<()> {
+        for (i, team) in teams.iter().enumerate() {
+            team_item(w, team, i == 0)?;
+        }
+        Ok(())
+    }
+
+    fn team_item<W: Write>(w: &mut W, team: &Team, champion: bool) -> Result<()> {
+        tpl! {
+            <li class={if champion { "champion" } else { "" }}>
+                <b>{team.name}</b>: {&team.score.to_string()}
+            </li>
+        }
+    }
+
+    pub fn comments<W: Write>(w: &mut W, comments: &[Comment]) -> Result<()> {
+        tpl! { <section>{!t comment_list(comments)}</section> }
+    }
+
+    fn comment_list<W: Write>(w: &mut W, comments: &[Comment]) -> Result<()> {
+        for c in comments {
+            comment(w, c)?;
+        }
+        Ok(())
+    }
+
+    fn comment<W: Write>(w: &mut W, c: &Comment) -> Result<()> {
+        tpl! { <article title={c.author}><p>{c.body}</p></article> }
+    }
+}
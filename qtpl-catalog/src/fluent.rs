@@ -0,0 +1,487 @@
+use crate::{Error, Message, Messages, Plural};
+use std::collections::HashMap;
+
+// A message or term as written, before references to others are resolved.
+struct Entry {
+    line: usize,
+    value: String,
+}
+
+pub fn parse(src: &str) -> Result<Messages, Error> {
+    let mut messages = HashMap::new();
+    let mut terms = HashMap::new();
+    // the entry being read, which continues on indented lines.
+    let mut current: Option<(String, bool, Entry, Vec<String>)> = None;
+    let mut finish = |current: &mut Option<(String, bool, Entry, Vec<String>)>| {
+        if let Some((id, term, mut entry, lines)) = current.take() {
+            let rest = dedent(&lines);
+            if entry.value.is_empty() {
+                // the value starts on the next line.
+                entry.value = rest.trim_start_matches('\n').to_owned();
+            } else {
+                entry.value.push_str(&rest);
+            }
+            if entry.value.is_empty() {
+                return Err(Error::new(entry.line, format!("`{}` has no value", id)));
+            }
+            let entries = if term { &mut terms } else { &mut messages };
+            entries.insert(id, entry);
+        }
+        Ok(())
+    };
+    for (i, line) in src.lines().enumerate() {
+        let n = i + 1;
+        // the closing brace of a selector may be unindented.
+        if line.starts_with(' ') || (current.is_some() && line.starts_with('}')) {
+            let rest = line.trim_start();
+            match &mut current {
+                Some(_) if rest.starts_with('.') => {
+                    return Err(Error::new(n, "attributes are not supported"))
+                }
+                Some((_, _, _, lines)) => lines.push(line.to_owned()),
+                None if rest.is_empty() => (),
+                None => return Err(Error::new(n, "expected a message")),
+            }
+            continue;
+        }
+        if line.is_empty() {
+            // blank lines are part of the value if it continues after them.
+            if let Some((_, _, _, lines)) = &mut current {
+                lines.push(String::new());
+            }
+            continue;
+        }
+        finish(&mut current)?;
+        if line.starts_with('#') {
+            continue;
+        }
+        let (id, value) = match line.find('=') {
+            Some(at) => (line[..at].trim_end(), line[at + 1..].trim()),
+            None => return Err(Error::new(n, "expected `id = value`")),
+        };
+        let (term, name) = match id.strip_prefix('-') {
+            Some(name) => (true, name),
+            None => (false, id),
+        };
+        if !is_identifier(name) {
+            return Err(Error::new(n, format!("invalid identifier `{}`", id)));
+        }
+        let entry = Entry {
+            line: n,
+            value: value.to_owned(),
+        };
+        current = Some((name.to_owned(), term, entry, vec![]));
+    }
+    finish(&mut current)?;
+
+    let resolver = Resolver {
+        messages: &messages,
+        terms: &terms,
+    };
+    // in the order they're written, so the first error is reported.
+    let mut entries: Vec<_> = messages.iter().collect();
+    entries.sort_by_key(|(_, e)| e.line);
+    let mut out = Messages::new();
+    for (id, entry) in entries {
+        let message = resolver.resolve(&entry.value, entry.line, &mut vec![id.clone()])?;
+        out.insert(id.clone(), message);
+    }
+    Ok(out)
+}
+
+// Joins the lines a value continues on, removing their common indentation,
+// which unindented closing braces don't count towards. Blank lines at the end
+// aren't part of the value.
+fn dedent(lines: &[String]) -> String {
+    let end = lines
+        .iter()
+        .rposition(|l| !l.trim().is_empty())
+        .map_or(0, |i| i + 1);
+    let lines = &lines[..end];
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('}'))
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|l| {
+            let l = if l.starts_with('}') {
+                l
+            } else {
+                l.get(indent..).unwrap_or("")
+            };
+            format!("\n{}", l)
+        })
+        .collect()
+}
+
+fn is_identifier(id: &str) -> bool {
+    let mut chars = id.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+struct Resolver<'a> {
+    messages: &'a HashMap<String, Entry>,
+    terms: &'a HashMap<String, Entry>,
+}
+
+impl Resolver<'_> {
+    // Parses the value, replacing references to other messages and terms with
+    // their value. The stack has the entries being resolved, to catch circular
+    // references.
+    fn resolve(&self, value: &str, line: usize, stack: &mut Vec<String>) -> Result<Message, Error> {
+        let err = |message: String| Error::new(line, message);
+        let mut message = Message::default();
+        // the selector, with the text before it and its variants, after which
+        // the message has the text following it.
+        let mut select: Option<(String, Message, Variants)> = None;
+        let mut rest = value;
+        while let Some(start) = rest.find('{') {
+            message.push_text(&rest[..start]);
+            let end = placeable_end(&rest[start..])
+                .ok_or_else(|| err("placeable is not closed, expected `}`".into()))?;
+            let inner = rest[start + 1..start + end].trim();
+            rest = &rest[start + end + 1..];
+            if inner.len() >= 2 && inner.starts_with('"') && inner.ends_with('"') {
+                message.push_text(&unescape(&inner[1..inner.len() - 1]).map_err(err)?);
+            } else if let Some((selector, variants)) = inner.split_once("->") {
+                if variants.contains("->") {
+                    return Err(err("nested selectors are not supported".into()));
+                }
+                if select.is_some() {
+                    return Err(err("only one selector is supported in a message".into()));
+                }
+                let selector = match selector.trim().strip_prefix('$') {
+                    Some(name) if is_identifier(name) => name,
+                    _ => {
+                        return Err(err(
+                            "selectors must be on a variable, as in `{ $count -> ... }`".into(),
+                        ))
+                    }
+                };
+                let variants = self.variants(variants, line, stack)?;
+                select = Some((selector.to_owned(), std::mem::take(&mut message), variants));
+            } else if inner.contains('(') {
+                return Err(err("functions are not supported".into()));
+            } else if let Some(name) = inner.strip_prefix('$') {
+                if !is_identifier(name) {
+                    return Err(err(format!("invalid variable `{}`", inner)));
+                }
+                message.push_placeholder(name);
+            } else {
+                let (entries, name) = match inner.strip_prefix('-') {
+                    Some(name) => (self.terms, name),
+                    None => (self.messages, inner),
+                };
+                if stack.iter().any(|s| s == inner) {
+                    return Err(err(format!("circular reference to `{}`", inner)));
+                }
+                match entries.get(name) {
+                    Some(e) => {
+                        stack.push(inner.to_owned());
+                        let resolved = self.resolve(&e.value, e.line, stack)?;
+                        stack.pop();
+                        if resolved.selector().is_some() {
+                            return Err(err(format!(
+                                "`{}` has a selector, so it can't be used in other messages",
+                                inner
+                            )));
+                        }
+                        message.extend(&resolved);
+                    }
+                    None => return Err(err(format!("unknown reference `{}`", inner))),
+                }
+            }
+        }
+        if rest.contains('}') {
+            return Err(err("unmatched `}`".into()));
+        }
+        message.push_text(rest);
+        let (selector, before, (default, variants)) = match select {
+            Some(select) => select,
+            None => return Ok(message),
+        };
+        // each form has the text around the selector.
+        let form = |variant: &Message| {
+            let mut form = before.clone();
+            form.extend(variant);
+            form.extend(&message);
+            form
+        };
+        let mut plural = Message::default();
+        plural.set_plural(&selector, form(&default));
+        for (category, variant) in &variants {
+            plural.push_form(*category, form(variant));
+        }
+        Ok(plural)
+    }
+
+    // Parses the variants of a selector, as in `[one] text`, each continuing
+    // on the lines up to the next one. Their keys must be plural categories,
+    // and the default one is marked with `*`.
+    fn variants(&self, src: &str, line: usize, stack: &mut Vec<String>) -> Result<Variants, Error> {
+        let err = |message: String| Error::new(line, message);
+        let mut written: Vec<(bool, &str, String)> = vec![];
+        for l in src.lines().map(str::trim) {
+            let (default, key) = match l.strip_prefix('*') {
+                Some(key) => (true, key),
+                None => (false, l),
+            };
+            if let Some(key) = key.strip_prefix('[') {
+                let end = key
+                    .find(']')
+                    .ok_or_else(|| err("variant key is not closed, expected `]`".into()))?;
+                written.push((default, key[..end].trim(), key[end + 1..].trim().to_owned()));
+            } else if let Some((_, _, text)) = written.last_mut() {
+                text.push('\n');
+                text.push_str(l);
+            } else if !l.is_empty() {
+                return Err(err("expected a variant, as in `[one] ...`".into()));
+            }
+        }
+        let mut default = None;
+        let mut variants: Vec<(Plural, Message)> = vec![];
+        for (is_default, key, text) in written {
+            let category = match Plural::from_name(key) {
+                Some(c) => c,
+                None if key.parse::<f64>().is_ok() => {
+                    return Err(err(format!(
+                        "numeric variant keys like `[{}]` are not supported, use plural categories like `[one]`",
+                        key
+                    )))
+                }
+                None => return Err(err(format!("unknown plural category `[{}]`", key))),
+            };
+            if variants.iter().any(|(c, _)| *c == category) {
+                return Err(err(format!("variant `[{}]` is given more than once", key)));
+            }
+            let variant = self.resolve(text.trim_end(), line, stack)?;
+            if is_default {
+                if default.is_some() {
+                    return Err(err("a selector can only have one default variant".into()));
+                }
+                default = Some(variant.clone());
+            }
+            variants.push((category, variant));
+        }
+        match default {
+            Some(default) => Ok((default, variants)),
+            None => Err(err(
+                "a selector needs a default variant, marked with `*` as in `*[other]`".into(),
+            )),
+        }
+    }
+}
+
+// The default variant of a selector, and all of them by plural category.
+type Variants = (Message, Vec<(Plural, Message)>);
+
+// The position of the `}` that closes the placeable at the start of the text,
+// skipping those in string literals and nested placeables. The variants of a
+// selector are text, where quotes aren't string literals.
+fn placeable_end(text: &str) -> Option<usize> {
+    // whether each open placeable has reached its variants.
+    let mut open: Vec<bool> = vec![];
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let variants = open.last() == Some(&true);
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' if !variants => in_string = !in_string,
+            _ if in_string => (),
+            '{' => open.push(false),
+            '}' => {
+                open.pop();
+                if open.is_empty() {
+                    return Some(i);
+                }
+            }
+            '-' if !variants && matches!(chars.peek(), Some((_, '>'))) => {
+                if let Some(v) = open.last_mut() {
+                    *v = true;
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+fn unescape(s: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => out.push('\\'),
+            Some('"') => out.push('"'),
+            Some('u') => {
+                let hex: String = chars.by_ref().take(4).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(c) if hex.len() == 4 => out.push(c),
+                    _ => return Err(format!("invalid escape `\\u{}`", hex)),
+                }
+            }
+            c => return Err(format!("invalid escape `\\{}`", c.unwrap_or(' '))),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Part, Plural};
+    use pretty_assertions::assert_eq;
+
+    fn parts(src: &str, key: &str) -> Vec<Part> {
+        parse(src).unwrap().get(key).unwrap().parts().to_vec()
+    }
+
+    #[test]
+    fn messages() {
+        let src = r#"
+# Comments are skipped.
+-brand = Acme
+hello = Hello, { $name }!
+welcome = Welcome to { -brand }. { hello }
+braces = Use { "{" } and { "A" }
+multi =
+    First line
+      indented
+
+    after a blank line
+"#;
+        assert_eq!(
+            parts(src, "hello"),
+            [
+                Part::Text("Hello, ".into()),
+                Part::Placeholder("name".into()),
+                Part::Text("!".into()),
+            ]
+        );
+        assert_eq!(
+            parts(src, "welcome"),
+            [
+                Part::Text("Welcome to Acme. Hello, ".into()),
+                Part::Placeholder("name".into()),
+                Part::Text("!".into()),
+            ]
+        );
+        assert_eq!(parts(src, "braces"), [Part::Text("Use { and A".into())]);
+        assert_eq!(
+            parts(src, "multi"),
+            [Part::Text(
+                "First line\n  indented\n\nafter a blank line".into()
+            )]
+        );
+        assert!(parse(src).unwrap().get("brand").is_none());
+    }
+
+    #[test]
+    fn selectors() {
+        let src = r#"
+-brand = Acme
+items = { $count ->
+    [one] One "item"
+   *[other] { $count } items
+} at { -brand }
+emails =
+    You have { $n ->
+        [zero] no emails
+        [one] an email
+       *[many] { $n } emails,
+            of which { $unread } are unread
+    }.
+"#;
+        let m = parse(src).unwrap();
+        let items = m.get("items").unwrap();
+        assert_eq!(items.selector(), Some("count"));
+        assert_eq!(
+            items.form(Plural::One),
+            [Part::Text("One \"item\" at Acme".into())]
+        );
+        let other = [
+            Part::Placeholder("count".into()),
+            Part::Text(" items at Acme".into()),
+        ];
+        assert_eq!(items.form(Plural::Other), other);
+        assert_eq!(items.form(Plural::Few), other);
+        assert_eq!(items.parts(), other);
+
+        let emails = m.get("emails").unwrap();
+        assert_eq!(emails.placeholders(), ["n", "unread"]);
+        assert_eq!(
+            emails.form(Plural::Zero),
+            [Part::Text("You have no emails.".into())]
+        );
+        // the default variant is used for categories without one.
+        assert_eq!(emails.form(Plural::Other), emails.form(Plural::Many));
+        assert_eq!(
+            emails.form(Plural::Many),
+            [
+                Part::Text("You have ".into()),
+                Part::Placeholder("n".into()),
+                Part::Text(" emails,\nof which ".into()),
+                Part::Placeholder("unread".into()),
+                Part::Text(" are unread.".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = |src: &str| parse(src).unwrap_err().to_string();
+        assert_eq!(
+            err("a = x\nb = { $n ->\n    [one] y\n }"),
+            "line 2: a selector needs a default variant, marked with `*` as in `*[other]`"
+        );
+        assert_eq!(
+            err("a = { $n ->\n    [0] none\n   *[other] y\n}"),
+            "line 1: numeric variant keys like `[0]` are not supported, use plural categories like `[one]`"
+        );
+        assert_eq!(
+            err("a = { $n ->\n    [single] y\n   *[other] y\n}"),
+            "line 1: unknown plural category `[single]`"
+        );
+        assert_eq!(
+            err("a = { -b ->\n   *[other] y\n}"),
+            "line 1: selectors must be on a variable, as in `{ $count -> ... }`"
+        );
+        assert_eq!(
+            err("a = { $n ->\n   *[other] { $m ->\n       *[other] y\n    }\n}"),
+            "line 1: nested selectors are not supported"
+        );
+        assert_eq!(
+            err("a = { $n ->\n   *[other] y\n}\nb = { a }"),
+            "line 4: `a` has a selector, so it can't be used in other messages"
+        );
+        assert_eq!(
+            err("a = x\n    .title = y"),
+            "line 2: attributes are not supported"
+        );
+        assert_eq!(
+            err("a = { missing }"),
+            "line 1: unknown reference `missing`"
+        );
+        assert_eq!(
+            err("a = { $n"),
+            "line 1: placeable is not closed, expected `}`"
+        );
+        assert_eq!(err("a ="), "line 1: `a` has no value");
+        assert_eq!(
+            err("a = { b }\nb = { a }"),
+            "line 2: circular reference to `a`"
+        );
+        assert_eq!(err("hello"), "line 1: expected `id = value`");
+    }
+}
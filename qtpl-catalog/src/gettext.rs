@@ -0,0 +1,171 @@
+use crate::{Error, Message, Messages};
+
+// The keyword a string belongs to, which it continues on following lines.
+#[derive(Clone, Copy, PartialEq)]
+enum Keyword {
+    Id,
+    Str,
+}
+
+pub fn parse(src: &str) -> Result<Messages, Error> {
+    let mut messages = Messages::new();
+    // the entry being read: the line it starts on, its id and translation.
+    let mut entry: Option<(usize, String, Option<String>)> = None;
+    let mut last = None;
+    let mut finish = |entry: &mut Option<(usize, String, Option<String>)>| {
+        if let Some((line, id, translation)) = entry.take() {
+            let translation = match translation {
+                Some(t) => t,
+                None => return Err(Error::new(line, "expected `msgstr`")),
+            };
+            // the header has an empty id.
+            if id.is_empty() {
+                return Ok(());
+            }
+            let text = if translation.is_empty() {
+                &id
+            } else {
+                &translation
+            };
+            let message = Message::parse(text).map_err(|err| Error::new(line, err))?;
+            messages.insert(id, message);
+        }
+        Ok(())
+    };
+    for (i, line) in src.lines().enumerate() {
+        let n = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('"') {
+            let s = string(line).map_err(|err| Error::new(n, err))?;
+            match (&mut entry, last) {
+                (Some((_, id, _)), Some(Keyword::Id)) => id.push_str(&s),
+                (Some((_, _, Some(t))), Some(Keyword::Str)) => t.push_str(&s),
+                _ => return Err(Error::new(n, "expected a keyword before the string")),
+            }
+            continue;
+        }
+        let (keyword, rest) = match line.find(char::is_whitespace) {
+            Some(at) => (&line[..at], line[at..].trim_start()),
+            None => (line, ""),
+        };
+        let s = || string(rest).map_err(|err| Error::new(n, err));
+        match keyword {
+            "msgid" => {
+                finish(&mut entry)?;
+                entry = Some((n, s()?, None));
+                last = Some(Keyword::Id);
+            }
+            "msgstr" => match &mut entry {
+                Some((_, _, t @ None)) => {
+                    *t = Some(s()?);
+                    last = Some(Keyword::Str);
+                }
+                _ => return Err(Error::new(n, "expected `msgid` before `msgstr`")),
+            },
+            "msgctxt" => return Err(Error::new(n, "contexts are not supported")),
+            k if k == "msgid_plural" || k.starts_with("msgstr[") => {
+                return Err(Error::new(
+                    n,
+                    "plural forms are not supported, use a Fluent catalog for plural messages",
+                ))
+            }
+            k => return Err(Error::new(n, format!("unknown keyword `{}`", k))),
+        }
+    }
+    finish(&mut entry)?;
+    Ok(messages)
+}
+
+// Parses a quoted string, the only thing allowed on the rest of the line.
+fn string(s: &str) -> Result<String, String> {
+    let inner = match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(inner) if s.len() >= 2 => inner,
+        _ => return Err("expected a quoted string".into()),
+    };
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('\\') => out.push('\\'),
+                Some('"') => out.push('"'),
+                c => return Err(format!("invalid escape `\\{}`", c.unwrap_or(' '))),
+            },
+            '"' => return Err("unescaped `\"` in string".into()),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Part;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn messages() {
+        let src = r#"
+# The header is skipped.
+msgid ""
+msgstr ""
+"Language: de\n"
+
+#: src/views.rs:10
+msgid "Hello, {name}!"
+msgstr "Hallo, {name}!"
+
+msgid "welcome"
+msgstr ""
+"Willkommen "
+"bei \"Acme\""
+
+msgid "Untranslated"
+msgstr ""
+"#;
+        let m = parse(src).unwrap();
+        assert_eq!(
+            m.get("Hello, {name}!").unwrap().parts(),
+            &[
+                Part::Text("Hallo, ".into()),
+                Part::Placeholder("name".into()),
+                Part::Text("!".into()),
+            ]
+        );
+        assert_eq!(
+            m.get("welcome").unwrap().parts(),
+            &[Part::Text("Willkommen bei \"Acme\"".into())]
+        );
+        assert_eq!(
+            m.get("Untranslated").unwrap().parts(),
+            &[Part::Text("Untranslated".into())]
+        );
+        assert!(m.get("").is_none());
+    }
+
+    #[test]
+    fn errors() {
+        let err = |src: &str| parse(src).unwrap_err().to_string();
+        assert_eq!(
+            err("msgid \"a\"\nmsgid_plural \"b\""),
+            "line 2: plural forms are not supported, use a Fluent catalog for plural messages"
+        );
+        assert_eq!(err("msgctxt \"x\""), "line 1: contexts are not supported");
+        assert_eq!(err("msgid \"a\""), "line 1: expected `msgstr`");
+        assert_eq!(
+            err("msgstr \"a\""),
+            "line 1: expected `msgid` before `msgstr`"
+        );
+        assert_eq!(err("msgid a"), "line 1: expected a quoted string");
+        assert_eq!(
+            err("msgid \"a\"\nmsgstr \"{b\""),
+            "line 1: placeholder is not closed, expected `}`"
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! Message catalogs, used by the `!i18n` directive of qtpl. This is shared by
+//! the runtime and the macros, which check templates against the default
+//! catalog. Use it through `qtpl::i18n` instead of directly.
+
+use std::collections::HashMap;
+use std::fmt;
+
+#[cfg(feature = "fluent")]
+mod fluent;
+#[cfg(feature = "gettext")]
+mod gettext;
+
+/// A part of a [`Message`](struct.Message.html).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Part {
+    /// Text written as is, other than being escaped.
+    Text(String),
+    /// The name of an argument, which is written in its place.
+    Placeholder(String),
+}
+
+/// The plural category of a number, which picks the form of the words used
+/// with it. Languages use different categories, and all use `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Plural {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl Plural {
+    /// All the categories, in the order CLDR lists them.
+    pub const ALL: [Plural; 6] = [
+        Plural::Zero,
+        Plural::One,
+        Plural::Two,
+        Plural::Few,
+        Plural::Many,
+        Plural::Other,
+    ];
+
+    /// The name of the category in CLDR, as in `one`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Plural::Zero => "zero",
+            Plural::One => "one",
+            Plural::Two => "two",
+            Plural::Few => "few",
+            Plural::Many => "many",
+            Plural::Other => "other",
+        }
+    }
+
+    /// The category with the name in CLDR.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|p| p.name() == name)
+    }
+}
+
+/// A message, made of text and placeholders for its arguments.
+///
+/// A message may instead have a form for each plural category of one of its
+/// arguments, the selector, as in "1 item" and "2 items".
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Message {
+    // the parts of the message, or of the form used for the categories
+    // without one.
+    parts: Vec<Part>,
+    selector: Option<String>,
+    forms: Vec<(Plural, Vec<Part>)>,
+}
+
+impl Message {
+    /// Parses a message with placeholders like `{name}`. Literal braces are
+    /// written as `{{` and `}}`.
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut message = Self::default();
+        let mut chars = src.chars().peekable();
+        let mut text = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err("placeholder is not closed, expected `}`".into()),
+                        }
+                    }
+                    let name = name.trim();
+                    if !is_name(name) {
+                        return Err(format!("invalid placeholder `{{{}}}`", name));
+                    }
+                    message.push_text(&text);
+                    text.clear();
+                    message.push_placeholder(name);
+                }
+                '}' => return Err("unmatched `}`, use `}}` for a literal brace".into()),
+                c => text.push(c),
+            }
+        }
+        message.push_text(&text);
+        Ok(message)
+    }
+
+    /// A message with a form for each plural category of the selector
+    /// argument, parsed like [`parse`](#method.parse). The `Other` form is
+    /// required, and is used for the categories without a form.
+    pub fn plural(selector: &str, forms: &[(Plural, &str)]) -> Result<Self, String> {
+        if !is_name(selector) {
+            return Err(format!("invalid selector `{}`", selector));
+        }
+        let other = match forms.iter().find(|(p, _)| *p == Plural::Other) {
+            Some((_, src)) => Self::parse(src)?,
+            None => return Err("the `other` form is required".into()),
+        };
+        let mut message = Self::default();
+        message.set_plural(selector, other);
+        for (category, src) in forms {
+            message.push_form(*category, Self::parse(src)?);
+        }
+        Ok(message)
+    }
+
+    /// The parts of the message, in order. For plural messages these are the
+    /// parts of the form used for the categories without one.
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// The argument whose plural category picks the form, for plural
+    /// messages.
+    pub fn selector(&self) -> Option<&str> {
+        self.selector.as_deref()
+    }
+
+    /// The parts of the form for the plural category, falling back to those of
+    /// [`parts`](#method.parts).
+    pub fn form(&self, category: Plural) -> &[Part] {
+        self.forms
+            .iter()
+            .find(|(c, _)| *c == category)
+            .map_or(&self.parts, |(_, parts)| parts)
+    }
+
+    /// The names of the placeholders in the message, in order of their first
+    /// use, starting with the selector. For plural messages these are those of
+    /// all its forms.
+    pub fn placeholders(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.selector.iter().map(String::as_str).collect();
+        let forms = self.forms.iter().map(|(_, parts)| parts);
+        for part in std::iter::once(&self.parts).chain(forms).flatten() {
+            if let Part::Placeholder(name) = part {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    // Makes the message plural, with the default form.
+    fn set_plural(&mut self, selector: &str, default: Message) {
+        self.selector = Some(selector.to_owned());
+        self.parts = default.parts;
+    }
+
+    // Adds the form for the category, replacing any it has.
+    fn push_form(&mut self, category: Plural, form: Message) {
+        self.forms.retain(|(c, _)| *c != category);
+        self.forms.push((category, form.parts));
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        match self.parts.last_mut() {
+            Some(Part::Text(t)) => t.push_str(text),
+            _ => self.parts.push(Part::Text(text.to_owned())),
+        }
+    }
+
+    fn push_placeholder(&mut self, name: &str) {
+        self.parts.push(Part::Placeholder(name.to_owned()));
+    }
+
+    #[cfg(feature = "fluent")]
+    fn extend(&mut self, other: &Message) {
+        for part in &other.parts {
+            match part {
+                Part::Text(t) => self.push_text(t),
+                Part::Placeholder(p) => self.push_placeholder(p),
+            }
+        }
+    }
+}
+
+// Whether the name can be used as a placeholder, which matches the names of
+// arguments given in templates.
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Messages by their key.
+#[derive(Debug, Clone, Default)]
+pub struct Messages {
+    messages: HashMap<String, Message>,
+}
+
+impl Messages {
+    /// An empty catalog.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a message, replacing any with the same key.
+    pub fn insert(&mut self, key: impl Into<String>, message: Message) {
+        self.messages.insert(key.into(), message);
+    }
+
+    /// The message for the key.
+    pub fn get(&self, key: &str) -> Option<&Message> {
+        self.messages.get(key)
+    }
+
+    /// The keys of all the messages, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.messages.keys().map(String::as_str)
+    }
+
+    /// Loads messages from a [Fluent](https://projectfluent.org) file. Messages
+    /// may use variables, string literals, and other messages and terms. A
+    /// message may have one selector, on the plural category of a variable,
+    /// whose variants are keyed by the categories, as in `[one]`. Functions,
+    /// attributes and other selectors aren't supported.
+    #[cfg(feature = "fluent")]
+    pub fn from_ftl(src: &str) -> Result<Self, Error> {
+        fluent::parse(src)
+    }
+
+    /// Loads messages from a gettext `.po` file, keyed by their `msgid`. The
+    /// translations use placeholders like `{name}`, and untranslated messages
+    /// use the `msgid` itself. Contexts and plural forms aren't supported, so
+    /// plural messages need a Fluent file.
+    #[cfg(feature = "gettext")]
+    pub fn from_po(src: &str) -> Result<Self, Error> {
+        gettext::parse(src)
+    }
+}
+
+/// An error in a catalog file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    /// The line of the file, starting from 1.
+    pub line: usize,
+    /// What's wrong.
+    pub message: String,
+}
+
+impl Error {
+    #[cfg(any(feature = "fluent", feature = "gettext"))]
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_message() {
+        let m = Message::parse("Hi {name}, {{literal}} {count} {name}").unwrap();
+        assert_eq!(
+            m.parts(),
+            &[
+                Part::Text("Hi ".into()),
+                Part::Placeholder("name".into()),
+                Part::Text(", {literal} ".into()),
+                Part::Placeholder("count".into()),
+                Part::Text(" ".into()),
+                Part::Placeholder("name".into()),
+            ]
+        );
+        assert_eq!(m.placeholders(), ["name", "count"]);
+        assert!(Message::parse("{name").is_err());
+        assert!(Message::parse("a } b").is_err());
+        assert!(Message::parse("{1x}").is_err());
+    }
+
+    #[test]
+    fn plural_message() {
+        let m = Message::plural(
+            "n",
+            &[(Plural::One, "one {item}"), (Plural::Other, "{n} {item}s")],
+        )
+        .unwrap();
+        assert_eq!(m.selector(), Some("n"));
+        assert_eq!(m.placeholders(), ["n", "item"]);
+        assert_eq!(m.form(Plural::One)[0], Part::Text("one ".into()));
+        assert_eq!(m.form(Plural::Few), m.parts());
+        assert_eq!(m.parts()[0], Part::Placeholder("n".into()));
+        assert_eq!(
+            Message::plural("n", &[(Plural::One, "one")]).unwrap_err(),
+            "the `other` form is required"
+        );
+        assert_eq!(Plural::from_name("few"), Some(Plural::Few));
+        assert_eq!(Plural::Many.name(), "many");
+    }
+}
@@ -0,0 +1,95 @@
+use quote::format_ident;
+use syn::parse::{Parse, ParseStream, Result};
+
+// Calls a template with the writer. Function and method calls get the writer
+// as their first argument, which is where `tplfn` adds it, and any other
+// callable, such as a closure, is called with just the writer.
+pub fn with_writer(e: &syn::Expr, w: syn::Expr) -> syn::Expr {
+    match e {
+        syn::Expr::Call(c) => {
+            let mut c = c.clone();
+            c.args.insert(0, w);
+            syn::Expr::Call(c)
+        }
+        syn::Expr::MethodCall(m) => {
+            let mut m = m.clone();
+            m.args.insert(0, w);
+            syn::Expr::MethodCall(m)
+        }
+        _ => syn::parse_quote!((#e)(#w)),
+    }
+}
+
+// The name of a constant `tplfn` defines next to a template function, such
+// as `HELLO_SIZE_HINT` for `hello`.
+pub fn const_ident(prefix: &str, f: &syn::Ident, suffix: &str) -> syn::Ident {
+    let name = f.to_string();
+    let name = name.trim_start_matches("r#").to_uppercase();
+    format_ident!("{}{}_{}", prefix, name, suffix, span = f.span())
+}
+
+// The input of the rendering macros: the template to call, and optionally the
+// number of bytes to allocate for the output up front, as in
+// `render!(home(x), size_hint = HOME_SIZE_HINT)`.
+pub struct Render {
+    pub template: syn::Expr,
+    pub size_hint: Option<syn::Expr>,
+}
+
+impl Parse for Render {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let template = input.parse()?;
+        let mut size_hint = None;
+        if input.parse::<Option<syn::Token![,]>>()?.is_some() && !input.is_empty() {
+            let name: syn::Ident = input.parse()?;
+            if name != "size_hint" {
+                return Err(syn::Error::new(name.span(), "expected `size_hint = ...`"));
+            }
+            input.parse::<syn::Token![=]>()?;
+            size_hint = Some(input.parse()?);
+            input.parse::<Option<syn::Token![,]>>()?;
+        }
+        Ok(Self {
+            template,
+            size_hint,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn call(e: &str) -> String {
+        let e: syn::Expr = syn::parse_str(e).unwrap();
+        let c = with_writer(&e, syn::parse_quote!(w));
+        quote!(#c).to_string()
+    }
+
+    fn hint(input: &str) -> Option<String> {
+        let r: Render = syn::parse_str(input).unwrap();
+        r.size_hint.map(|h| quote!(#h).to_string())
+    }
+
+    #[test]
+    fn writer_argument() {
+        assert_eq!(call("home(x)"), "home (w , x)");
+        assert_eq!(call("views::home::<T>()"), "views :: home :: < T > (w)");
+        assert_eq!(call("self.page(x)"), "self . page (w , x)");
+        assert_eq!(call("item.render::<T>()"), "item . render :: < T > (w)");
+        assert_eq!(call("|w| home(w, x)"), "(| w | home (w , x)) (w)");
+        assert_eq!(call("self.sidebar"), "(self . sidebar) (w)");
+    }
+
+    #[test]
+    fn size_hint() {
+        assert_eq!(hint("home(x)"), None);
+        assert_eq!(hint("home(x),"), None);
+        assert_eq!(
+            hint("home(x), size_hint = views::HOME_SIZE_HINT").as_deref(),
+            Some("views :: HOME_SIZE_HINT")
+        );
+        assert!(syn::parse_str::<Render>("home(x), hint = 1").is_err());
+    }
+}
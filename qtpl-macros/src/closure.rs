@@ -0,0 +1,44 @@
+use crate::tpl::Template;
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream, Result};
+
+// A template written as a closure taking the writer, `move |w| { ... }`.
+pub struct Closure {
+    capture: Option<syn::Token![move]>,
+    template: Template,
+}
+
+impl Parse for Closure {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let capture = input.parse()?;
+        input.parse::<syn::Token![|]>()?;
+        let w: syn::Ident = input.parse()?;
+        if w != "w" {
+            abort!(
+                w, "the writer must be named `w`";
+                help = "templates write to `w`, use `|w|` instead",
+            );
+        }
+        input.parse::<syn::Token![|]>()?;
+        let content;
+        syn::braced!(content in input);
+        Ok(Self {
+            capture,
+            template: content.parse()?,
+        })
+    }
+}
+
+impl ToTokens for Closure {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let (capture, template) = (&self.capture, &self.template);
+        quote! {
+            #capture |w: &mut dyn ::std::io::Write| -> ::std::io::Result<()> {
+                #template
+            }
+        }
+        .to_tokens(tokens);
+    }
+}
@@ -0,0 +1,161 @@
+use proc_macro2::Span;
+use proc_macro_error::emit_error;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Defaults for the template options of a crate, from the
+// `[package.metadata.qtpl]` table of its Cargo.toml:
+//
+//     [package.metadata.qtpl]
+//     whitespace = "minify"
+//     whitespace_insensitive = "my-card my-row"
+//     block_size_hint = 32
+//     i18n_catalog = "locales/en.ftl"
+#[derive(Clone, Default)]
+pub struct Config {
+    pub whitespace: Option<String>,
+    pub whitespace_insensitive: Option<String>,
+    pub block_size_hint: Option<usize>,
+    pub i18n_catalog: Option<String>,
+    // the manifest the config was read from, which templates include so
+    // changing it rebuilds them.
+    pub manifest: Option<String>,
+}
+
+impl Config {
+    // Reads the config of the crate being built, reporting an error if the
+    // table is invalid.
+    pub fn load() -> Self {
+        let dir = match std::env::var("CARGO_MANIFEST_DIR") {
+            Ok(dir) => dir,
+            Err(_) => return Self::default(),
+        };
+        let path = PathBuf::from(dir).join("Cargo.toml");
+        match load(&path) {
+            Ok(config) => config,
+            Err(err) => {
+                emit_error!(
+                    Span::call_site(), "invalid [package.metadata.qtpl] in {}: {}", path.display(), err;
+                    help = "the keys are `whitespace`, `whitespace_insensitive`, `block_size_hint` and `i18n_catalog`",
+                );
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(src: &str) -> Result<Self, String> {
+        let manifest: toml::Table = src
+            .parse()
+            .map_err(|err: toml::de::Error| err.to_string())?;
+        let table = match manifest
+            .get("package")
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.get("qtpl"))
+        {
+            Some(toml::Value::Table(t)) => t,
+            Some(_) => return Err("expected a table".into()),
+            None => return Ok(Self::default()),
+        };
+        let mut config = Self::default();
+        for (key, value) in table {
+            let string = || match value {
+                toml::Value::String(s) => Ok(s.clone()),
+                _ => Err(format!("`{}` must be a string", key)),
+            };
+            match key.as_str() {
+                "whitespace" => config.whitespace = Some(string()?),
+                "whitespace_insensitive" => config.whitespace_insensitive = Some(string()?),
+                "i18n_catalog" => config.i18n_catalog = Some(string()?),
+                "block_size_hint" => match value {
+                    toml::Value::Integer(i) if *i >= 0 => {
+                        config.block_size_hint = Some(*i as usize)
+                    }
+                    _ => return Err("`block_size_hint` must be a number of bytes".into()),
+                },
+                _ => return Err(format!("unknown key `{}`", key)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+type Loaded = (Option<SystemTime>, Result<Config, String>);
+
+thread_local! {
+    // The manifest is read once for all the templates in the crate, and again
+    // if it changes, as the macros may live on in tools like IDEs.
+    static CONFIGS: RefCell<HashMap<PathBuf, Loaded>> = RefCell::new(HashMap::new());
+}
+
+fn load(path: &Path) -> Result<Config, String> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    CONFIGS.with(|configs| {
+        let mut configs = configs.borrow_mut();
+        match configs.get(path) {
+            Some((m, loaded)) if modified.is_some() && *m == modified => loaded.clone(),
+            _ => {
+                let loaded = match std::fs::read_to_string(path) {
+                    Ok(src) => Config::parse(&src).map(|config| Config {
+                        manifest: Some(path.to_string_lossy().into_owned()),
+                        ..config
+                    }),
+                    // without a manifest there's nothing to configure.
+                    Err(_) => Ok(Config::default()),
+                };
+                configs.insert(path.to_owned(), (modified, loaded.clone()));
+                loaded
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata() {
+        let config = Config::parse(
+            r#"
+            [package]
+            name = "x"
+
+            [package.metadata.qtpl]
+            whitespace = "minify"
+            whitespace_insensitive = "my-card"
+            block_size_hint = 32
+            i18n_catalog = "locales/en.ftl"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.whitespace.as_deref(), Some("minify"));
+        assert_eq!(config.whitespace_insensitive.as_deref(), Some("my-card"));
+        assert_eq!(config.block_size_hint, Some(32));
+        assert_eq!(config.i18n_catalog.as_deref(), Some("locales/en.ftl"));
+    }
+
+    #[test]
+    fn no_metadata() {
+        let config = Config::parse("[package]\nname = \"x\"\n").unwrap();
+        assert!(config.whitespace.is_none() && config.block_size_hint.is_none());
+    }
+
+    #[test]
+    fn invalid_metadata() {
+        let err = |src: &str| Config::parse(src).err().unwrap();
+        assert_eq!(
+            err("[package.metadata.qtpl]\nblock_size_hint = \"32\""),
+            "`block_size_hint` must be a number of bytes"
+        );
+        assert_eq!(
+            err("[package.metadata.qtpl]\nwhitespace = 1"),
+            "`whitespace` must be a string"
+        );
+        assert_eq!(
+            err("[package.metadata.qtpl]\nminify = true"),
+            "unknown key `minify`"
+        );
+    }
+}
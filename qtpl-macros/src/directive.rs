@@ -0,0 +1,120 @@
+use proc_macro_error::{abort, emit_error};
+
+// The formatting directives, used as `{!name expr}`, along with what they
+// write.
+pub const DIRECTIVES: &[(&str, &str)] = &[
+    ("a", "an escaped and quoted attribute value"),
+    ("b", "bytes as they are"),
+    ("t", "the output of another template function"),
+    ("i18n", "a message from the catalog"),
+    ("number", "a number formatted for the locale"),
+    ("currency", "an amount of money formatted for the locale"),
+    ("date", "a date formatted for the locale"),
+    ("plural", "the plural form for a number in the locale"),
+];
+
+// Directives defined using `directive(name = "path")`, which call the function
+// at the path with the writer and a reference to the value.
+pub type Custom = Vec<(String, syn::Path)>;
+
+// Adds the directives defined by a `directive(...)` option.
+pub fn define(list: syn::MetaList, custom: &mut Custom) {
+    for nested in list.nested {
+        if let syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+            path,
+            lit: syn::Lit::Str(s),
+            ..
+        })) = &nested
+        {
+            if let Some(name) = path.get_ident() {
+                if DIRECTIVES.iter().any(|(d, _)| name == d) {
+                    emit_error!(name, "`!{}` is a built in directive", name);
+                    continue;
+                }
+                match s.parse::<syn::Path>() {
+                    Ok(p) => custom.push((name.to_string(), p)),
+                    Err(err) => emit_error!(s, "invalid function path: {}", err),
+                }
+                continue;
+            }
+        }
+        emit_error!(nested, "expected `name = \"path::to::function\"`");
+    }
+}
+
+// Aborts with an error for a directive that doesn't exist, listing the ones
+// that do and suggesting the closest match.
+pub fn unknown(name: &syn::Ident, custom: &Custom) -> ! {
+    let valid = DIRECTIVES
+        .iter()
+        .map(|(d, desc)| format!("`!{}` for {}", d, desc))
+        .chain(custom.iter().map(|(d, _)| format!("`!{}`", d)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let names = DIRECTIVES
+        .iter()
+        .map(|(d, _)| *d)
+        .chain(custom.iter().map(|(d, _)| d.as_str()));
+    match suggest(&name.to_string(), names) {
+        Some(d) => abort!(
+            name, "unknown formatting directive `!{}`", name;
+            help = "did you mean `!{}`?", d;
+            note = "valid directives are {}", valid,
+        ),
+        None => abort!(
+            name, "unknown formatting directive `!{}`", name;
+            note = "valid directives are {}", valid,
+        ),
+    }
+}
+
+// Returns the candidate closest to `name`, if any is close enough to be a
+// likely typo. Candidates that `name` starts with are preferred, since
+// directives are short and are often mistaken for the word they abbreviate,
+// as in `!attr` for `!a`.
+pub fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .map(|c| (!name.starts_with(c), distance(name, c), c))
+        .filter(|(not_prefix, d, c)| !not_prefix || *d <= c.len().max(name.len()) / 3)
+        .min()
+        .map(|(_, _, c)| c)
+}
+
+// The Levenshtein distance between the strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(cur).min(row[j])
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggestions() {
+        let names = || DIRECTIVES.iter().map(|(d, _)| *d);
+        assert_eq!(suggest("att", names()), Some("a"));
+        assert_eq!(suggest("bytes", names()), Some("b"));
+        assert_eq!(suggest("tpl", names()), Some("t"));
+        assert_eq!(suggest("x", names()), None);
+        assert_eq!(
+            suggest("mony", ["money", "date"].iter().copied()),
+            Some("money")
+        );
+        assert_eq!(suggest("html", ["money", "date"].iter().copied()), None);
+    }
+}
@@ -0,0 +1,109 @@
+use proc_macro_error::emit_error;
+use quote::quote;
+use syn::spanned::Spanned;
+
+// The built in filters, which are functions in `qtpl::filters` taking a
+// reference to the value and the arguments.
+pub const FILTERS: &[&str] = &[
+    "capitalize",
+    "default",
+    "join",
+    "lower",
+    "pluralize",
+    "trim",
+    "truncate",
+    "upper",
+    "urlencode",
+];
+
+// A single step in a filter chain like `{value | f | g(x)}`.
+pub struct Filter {
+    pub path: syn::Path,
+    pub args: Vec<syn::Expr>,
+}
+
+impl Filter {
+    fn from_expr(e: &syn::Expr) -> Option<Self> {
+        match e {
+            syn::Expr::Path(p) => Some(Self {
+                path: p.path.clone(),
+                args: vec![],
+            }),
+            syn::Expr::Call(syn::ExprCall { func, args, .. }) => match &**func {
+                syn::Expr::Path(p) => Some(Self {
+                    path: p.path.clone(),
+                    args: args.iter().cloned().collect(),
+                }),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // The name of the filter if it's a built in one.
+    pub fn builtin(&self) -> Option<&'static str> {
+        let name = self.path.get_ident()?;
+        FILTERS.iter().copied().find(|f| name == f)
+    }
+
+    // Applies a built in filter to the value.
+    pub fn apply(self, name: &str, value: syn::Expr) -> syn::Expr {
+        let f = syn::Ident::new(name, self.path.span());
+        let mut args = self.args;
+        if name == "pluralize" {
+            // the suffixes default to `s` for the plural, and nothing for the
+            // singular.
+            match args.len() {
+                0 => args = vec![syn::parse_quote!(""), syn::parse_quote!("s")],
+                1 => args.insert(0, syn::parse_quote!("")),
+                _ => (),
+            }
+        }
+        syn::parse_quote! { ::qtpl::filters::#f(&(#value), #(#args),*) }
+    }
+}
+
+// Splits an expression like `value | f | g(x)` into the value and the
+// filters, or returns `None` if it isn't a filter chain. Anything after a `|`
+// that isn't a path or a call to a path is a bitwise or, as in `{a | 1}`.
+pub fn chain(e: &syn::Expr) -> Option<(syn::Expr, Vec<Filter>)> {
+    let mut filters = vec![];
+    let mut e = e;
+    while let syn::Expr::Binary(syn::ExprBinary {
+        left,
+        op: syn::BinOp::BitOr(_),
+        right,
+        ..
+    }) = e
+    {
+        filters.push(Filter::from_expr(right)?);
+        e = left;
+    }
+    if filters.is_empty() {
+        return None;
+    }
+    filters.reverse();
+    Some((e.clone(), filters))
+}
+
+// Reports a filter that writes the output directly, such as a custom
+// directive, being used before other filters.
+pub fn not_last(f: &Filter) {
+    let path = &f.path;
+    emit_error!(
+        path, "`{}` writes the output, and must be the last filter",
+        quote!(#path).to_string().replace(' ', "");
+        help = "the built in filters are: {}", FILTERS.join(", "),
+    );
+}
+
+// Reports a name used as the last filter which isn't a filter or a directive,
+// since `{a | b}` is more likely a bitwise or than a function in scope.
+pub fn unknown(f: &Filter) {
+    let path = &f.path;
+    emit_error!(
+        path, "`{}` isn't a built in filter or a directive", quote!(#path);
+        help = "for a bitwise or, wrap the expression in parens, as in `{{(a | {})}}`", quote!(#path);
+        note = "to write the value using a function, define a directive for it, or use its path, as in `{{value | self::{}}}`", quote!(#path);
+    );
+}
@@ -0,0 +1,505 @@
+use proc_macro2::Span;
+use proc_macro_error::emit_error;
+
+// Elements which never have content, and so must never be closed.
+const VOID: &[&str] = &[
+    "!doctype", "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta",
+    "param", "source", "track", "wbr",
+];
+
+// Elements whose closing tag may be omitted, and are implicitly closed by
+// their parent.
+const OPTIONAL_END: &[&str] = &[
+    "body", "caption", "colgroup", "dd", "dt", "head", "html", "li", "optgroup", "option", "p",
+    "rp", "rt", "tbody", "td", "tfoot", "th", "thead", "tr",
+];
+
+// Elements whose start tag closes an open `<p>`, as a paragraph can't
+// contain them. See https://html.spec.whatwg.org/#parsing-main-inbody.
+const CLOSES_P: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "center",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "li",
+    "listing",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "plaintext",
+    "pre",
+    "search",
+    "section",
+    "summary",
+    "table",
+    "ul",
+    "xmp",
+];
+
+// Elements that stop the search for an open `<p>` to close, as the "button
+// scope" of the spec.
+const BUTTON_SCOPE: &[&str] = &[
+    "applet", "button", "caption", "html", "marquee", "object", "table", "td", "template", "th",
+];
+
+// Known HTML elements, used by `#![lint]`.
+const ELEMENTS: &[&str] = &[
+    "a",
+    "abbr",
+    "address",
+    "area",
+    "article",
+    "aside",
+    "audio",
+    "b",
+    "base",
+    "bdi",
+    "bdo",
+    "blockquote",
+    "body",
+    "br",
+    "button",
+    "canvas",
+    "caption",
+    "cite",
+    "code",
+    "col",
+    "colgroup",
+    "data",
+    "datalist",
+    "dd",
+    "del",
+    "details",
+    "dfn",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "embed",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "i",
+    "iframe",
+    "img",
+    "input",
+    "ins",
+    "kbd",
+    "label",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "map",
+    "mark",
+    "math",
+    "menu",
+    "meta",
+    "meter",
+    "nav",
+    "noscript",
+    "object",
+    "ol",
+    "optgroup",
+    "option",
+    "output",
+    "p",
+    "param",
+    "picture",
+    "pre",
+    "progress",
+    "q",
+    "rp",
+    "rt",
+    "ruby",
+    "s",
+    "samp",
+    "script",
+    "search",
+    "section",
+    "select",
+    "slot",
+    "small",
+    "source",
+    "span",
+    "strong",
+    "style",
+    "sub",
+    "summary",
+    "sup",
+    "svg",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "textarea",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "title",
+    "tr",
+    "track",
+    "u",
+    "ul",
+    "var",
+    "video",
+    "wbr",
+];
+
+// Known HTML attributes, used by `#![lint]`. This includes global attributes
+// and those specific to any element, from the index of the HTML standard.
+// Event handlers are matched by their `on` prefix instead, as new events are
+// added often.
+const ATTRIBUTES: &[&str] = &[
+    "abbr",
+    "accept",
+    "accept-charset",
+    "accesskey",
+    "action",
+    "allow",
+    "allowfullscreen",
+    "alpha",
+    "alt",
+    "as",
+    "async",
+    "autocapitalize",
+    "autocomplete",
+    "autocorrect",
+    "autofocus",
+    "autoplay",
+    "blocking",
+    "charset",
+    "checked",
+    "cite",
+    "class",
+    "closedby",
+    "color",
+    "colorspace",
+    "cols",
+    "colspan",
+    "command",
+    "commandfor",
+    "content",
+    "contenteditable",
+    "controls",
+    "coords",
+    "crossorigin",
+    "data",
+    "datetime",
+    "decoding",
+    "default",
+    "defer",
+    "dir",
+    "dirname",
+    "disabled",
+    "download",
+    "draggable",
+    "enctype",
+    "enterkeyhint",
+    "exportparts",
+    "fetchpriority",
+    "for",
+    "form",
+    "formaction",
+    "formenctype",
+    "formmethod",
+    "formnovalidate",
+    "formtarget",
+    "headers",
+    "headingoffset",
+    "headingreset",
+    "height",
+    "hidden",
+    "high",
+    "href",
+    "hreflang",
+    "http-equiv",
+    "id",
+    "imagesizes",
+    "imagesrcset",
+    "inert",
+    "inputmode",
+    "integrity",
+    "is",
+    "ismap",
+    "itemid",
+    "itemprop",
+    "itemref",
+    "itemscope",
+    "itemtype",
+    "kind",
+    "label",
+    "lang",
+    "list",
+    "loading",
+    "loop",
+    "low",
+    "max",
+    "maxlength",
+    "media",
+    "method",
+    "min",
+    "minlength",
+    "multiple",
+    "muted",
+    "name",
+    "nomodule",
+    "nonce",
+    "novalidate",
+    "open",
+    "optimum",
+    "part",
+    "pattern",
+    "ping",
+    "placeholder",
+    "playsinline",
+    "popover",
+    "popovertarget",
+    "popovertargetaction",
+    "poster",
+    "preload",
+    "readonly",
+    "referrerpolicy",
+    "rel",
+    "required",
+    "reversed",
+    "role",
+    "rows",
+    "rowspan",
+    "sandbox",
+    "scope",
+    "selected",
+    "shadowrootclonable",
+    "shadowrootcustomelementregistry",
+    "shadowrootdelegatesfocus",
+    "shadowrootmode",
+    "shadowrootserializable",
+    "shape",
+    "size",
+    "sizes",
+    "slot",
+    "span",
+    "spellcheck",
+    "src",
+    "srcdoc",
+    "srclang",
+    "srcset",
+    "start",
+    "step",
+    "style",
+    "tabindex",
+    "target",
+    "title",
+    "translate",
+    "type",
+    "usemap",
+    "value",
+    "width",
+    "wrap",
+    "writingsuggestions",
+    "xmlns",
+];
+
+// Elements whose content is not HTML, and so isn't linted.
+const FOREIGN: &[&str] = &["math", "svg"];
+
+// Reports an element name that isn't a known HTML element. Custom elements,
+// which must contain a dash, are always allowed.
+pub fn lint_element(name: &str, span: Span) {
+    let name = tag_name(name);
+    if !name.starts_with('!') && !name.contains('-') && !ELEMENTS.contains(&name.as_str()) {
+        emit_error!(span, "unknown element `<{}>`", name);
+    }
+}
+
+// Reports an attribute name that isn't a known HTML attribute. The `data-*`
+// and `aria-*` families, event handlers like `onclick`, and namespaced names
+// like `xml:lang` are always allowed.
+pub fn lint_attribute(name: &str, span: Span) {
+    let name = name.to_ascii_lowercase();
+    let handler = name
+        .strip_prefix("on")
+        .is_some_and(|event| !event.is_empty() && event.bytes().all(|b| b.is_ascii_lowercase()));
+    let allowed = name.starts_with("data-")
+        || name.starts_with("aria-")
+        || handler
+        || name.contains(':')
+        || ATTRIBUTES.contains(&name.as_str());
+    if !allowed {
+        emit_error!(span, "unknown attribute `{}`", name);
+    }
+}
+
+pub fn is_void(name: &str) -> bool {
+    VOID.contains(&tag_name(name).as_str())
+}
+
+// Normalizes a tag name as it appears in a template for lookups, dropping the
+// trailing slash of a self closing tag like `<br/>`.
+pub fn tag_name(name: &str) -> String {
+    name.trim_end_matches('/').to_ascii_lowercase()
+}
+
+// Tracks the currently open elements to check that tags are well formed,
+// reporting unclosed, mismatched and misnested tags. Fragments are templates
+// that are intentionally partial, and may close elements they didn't open,
+// or leave elements open at the end.
+//
+// Of the implied end tags of HTML, only those closing a `<p>` are tracked, so
+// a `</p>` after a block started inside the paragraph is reported.
+pub struct TagStack {
+    fragment: bool,
+    open: Vec<(String, Span)>,
+    // the block that last closed a `<p>`, and its position in `open`, until
+    // another `<p>` opens or the element containing them is closed.
+    closed_p: Option<(String, usize)>,
+}
+
+impl TagStack {
+    pub fn new(fragment: bool) -> Self {
+        Self {
+            fragment,
+            open: vec![],
+            closed_p: None,
+        }
+    }
+
+    pub fn open(&mut self, name: &str, span: Span) {
+        let name = tag_name(name);
+        if name == "p" {
+            self.closed_p = None;
+        }
+        if CLOSES_P.contains(&name.as_str()) {
+            self.close_p(&name);
+        }
+        if !is_void(&name) {
+            self.open.push((name, span));
+        }
+    }
+
+    // Called for tags closed as part of the opening tag, like `<svg/>`.
+    pub fn self_close(&mut self, name: &str) {
+        let name = tag_name(name);
+        if self.open.last().map(|(n, _)| n) == Some(&name) {
+            self.open.pop();
+        }
+    }
+
+    pub fn close(&mut self, name: &str, span: Span) {
+        let name = tag_name(name);
+        if is_void(&name) {
+            emit_error!(span, "void element `<{}>` must not be closed", name);
+            return;
+        }
+        match self.open.iter().rposition(|(n, _)| *n == name) {
+            Some(i) => {
+                self.unclosed(i + 1, &format!("</{}>", name));
+                self.open.pop();
+                if matches!(self.closed_p, Some((_, at)) if at > i) {
+                    self.closed_p = None;
+                }
+            }
+            None if name == "p" && self.closed_p.is_some() => {
+                let block = self.closed_p.as_ref().map_or("", |(b, _)| b);
+                emit_error!(
+                    span,
+                    "closing tag `</p>` has no matching opening tag";
+                    note = "the `<p>` was already closed by the `<{}>` inside it", block;
+                    help = "a `<p>` can't contain blocks, so close it before the block or use a `<div>`",
+                );
+            }
+            None if self.fragment => self.unclosed(0, &format!("</{}>", name)),
+            None => emit_error!(
+                span,
+                "closing tag `</{}>` has no matching opening tag", name;
+                help = "use `#![fragment]` if the template is intentionally partial",
+            ),
+        }
+    }
+
+    // The number of elements currently open.
+    pub fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    // Whether we're inside an element with foreign content like `<svg>`.
+    pub fn in_foreign(&self) -> bool {
+        self.open.iter().any(|(n, _)| FOREIGN.contains(&n.as_str()))
+    }
+
+    pub fn finish(self) {
+        if self.fragment {
+            return;
+        }
+        for (name, span) in self.open {
+            if !OPTIONAL_END.contains(&name.as_str()) {
+                emit_error!(
+                    span,
+                    "`<{}>` is not closed by the end of the template", name;
+                    help = "use `#![fragment]` if the template is intentionally partial",
+                );
+            }
+        }
+    }
+
+    // Closes the `<p>` in button scope, if any, as the `block` starts.
+    fn close_p(&mut self, block: &str) {
+        let i = self.open.iter().rposition(|(n, _)| {
+            n == "p" || BUTTON_SCOPE.contains(&n.as_str()) || FOREIGN.contains(&n.as_str())
+        });
+        if let Some(i) = i.filter(|&i| self.open[i].0 == "p") {
+            self.unclosed(i + 1, &format!("<{}>", block));
+            self.open.pop();
+            self.closed_p = Some((block.to_owned(), self.open.len()));
+        }
+    }
+
+    // Removes the elements open from `from` onwards as they're being closed by
+    // the tag `closing`, reporting those that required a closing tag.
+    fn unclosed(&mut self, from: usize, closing: &str) {
+        for (name, span) in self.open.drain(from..) {
+            if !OPTIONAL_END.contains(&name.as_str()) {
+                emit_error!(span, "`<{}>` is not closed before `{}`", name, closing);
+            }
+        }
+    }
+}
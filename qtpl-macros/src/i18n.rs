@@ -0,0 +1,236 @@
+use crate::directive;
+use proc_macro2::{Span, TokenStream};
+use proc_macro_error::emit_error;
+use qtpl_catalog::Messages;
+use quote::{quote, ToTokens};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+use syn::parse::{Parse, ParseStream, Result};
+
+// The catalog templates are checked against, given by the `i18n_catalog`
+// option or in `[package.metadata.qtpl]`. The path is relative to the crate
+// being built.
+#[derive(Clone)]
+pub struct Catalog {
+    path: String,
+    span: Span,
+}
+
+impl Catalog {
+    pub fn new(path: String, span: Span) -> Self {
+        Self { path, span }
+    }
+
+    fn full_path(&self) -> PathBuf {
+        let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+        PathBuf::from(dir).join(&self.path)
+    }
+
+    // Loads the messages, reporting an error at the option if the catalog
+    // can't be read or parsed.
+    fn load(&self) -> Option<Rc<Messages>> {
+        let path = self.full_path();
+        let loaded = load(&path);
+        if let Err(err) = &loaded {
+            emit_error!(self.span, "invalid catalog {}: {}", self.path, err);
+        }
+        loaded.ok()
+    }
+}
+
+type Loaded = (
+    Option<SystemTime>,
+    std::result::Result<Rc<Messages>, String>,
+);
+
+thread_local! {
+    // Catalogs are parsed once for all the templates in the crate, and again
+    // if the file changes, as the macros may live on in tools like IDEs.
+    static CATALOGS: RefCell<HashMap<PathBuf, Loaded>> = RefCell::new(HashMap::new());
+}
+
+fn load(path: &Path) -> std::result::Result<Rc<Messages>, String> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    CATALOGS.with(|catalogs| {
+        let mut catalogs = catalogs.borrow_mut();
+        match catalogs.get(path) {
+            Some((m, loaded)) if modified.is_some() && *m == modified => loaded.clone(),
+            _ => {
+                let loaded = parse(path);
+                catalogs.insert(path.to_owned(), (modified, loaded.clone()));
+                loaded
+            }
+        }
+    })
+}
+
+type Parser = fn(&str) -> std::result::Result<Messages, qtpl_catalog::Error>;
+
+// Each format is only parsed with the feature of qtpl enabling it.
+fn parse(path: &Path) -> std::result::Result<Rc<Messages>, String> {
+    let parser: std::result::Result<Parser, &str> = match path.extension().and_then(|e| e.to_str())
+    {
+        #[cfg(feature = "fluent")]
+        Some("ftl") => Ok(Messages::from_ftl),
+        #[cfg(not(feature = "fluent"))]
+        Some("ftl") => Err("`.ftl` catalogs need the `fluent` feature of qtpl"),
+        #[cfg(feature = "gettext")]
+        Some("po") => Ok(Messages::from_po),
+        #[cfg(not(feature = "gettext"))]
+        Some("po") => Err("`.po` catalogs need the `gettext` feature of qtpl"),
+        _ => Err("unknown format, expected a `.ftl` or `.po` file"),
+    };
+    let parser = parser.map_err(String::from)?;
+    let src = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    parser(&src).map(Rc::new).map_err(|err| err.to_string())
+}
+
+// A message from the catalog, `{!i18n "key", name = value, ...}`, or a plural
+// one written in the form for the locale named `locale` in scope,
+// `{!plural "key", count = n, ...}`. Inside a tag it's written as a quoted
+// attribute value.
+pub struct Message {
+    key: syn::LitStr,
+    args: Vec<(syn::Ident, syn::Expr)>,
+    pub plural: bool,
+    pub quoted: bool,
+    // the catalog the message was checked against, so changing it rebuilds the
+    // template.
+    checked: Option<String>,
+}
+
+impl Parse for Message {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let key = input.parse()?;
+        let mut args: Vec<(syn::Ident, syn::Expr)> = vec![];
+        while !input.is_empty() {
+            input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name: syn::Ident = input.parse()?;
+            input.parse::<syn::Token![=]>()?;
+            let value = input.parse()?;
+            if args.iter().any(|(n, _)| *n == name) {
+                emit_error!(name, "argument `{}` is given more than once", name);
+            }
+            args.push((name, value));
+        }
+        Ok(Self {
+            key,
+            args,
+            plural: false,
+            quoted: false,
+            checked: None,
+        })
+    }
+}
+
+impl Message {
+    // Checks the key exists in the default catalog, and that the arguments
+    // match the placeholders of the message.
+    pub fn check(&mut self, catalog: Option<&Catalog>) {
+        let catalog = match catalog {
+            Some(c) => c,
+            None => {
+                emit_error!(
+                    self.key, "no catalog to check messages against";
+                    help = "set the default catalog using `#![i18n_catalog = \"path/to/catalog.ftl\"]` or `i18n_catalog` in [package.metadata.qtpl]",
+                );
+                return;
+            }
+        };
+        let messages = match catalog.load() {
+            Some(m) => m,
+            None => return,
+        };
+        self.checked = Some(catalog.full_path().to_string_lossy().into_owned());
+        let key = self.key.value();
+        let message = match messages.get(&key) {
+            Some(m) => m,
+            None => {
+                match directive::suggest(&key, messages.keys()) {
+                    Some(k) => emit_error!(
+                        self.key, "unknown message `{}`", key;
+                        help = "did you mean `{}`?", k;
+                        note = "messages are checked against {}", catalog.path,
+                    ),
+                    None => emit_error!(
+                        self.key, "unknown message `{}`", key;
+                        note = "messages are checked against {}", catalog.path,
+                    ),
+                }
+                return;
+            }
+        };
+        match (message.selector(), self.plural) {
+            (Some(selector), false) => emit_error!(
+                self.key, "message `{}` has plural forms", key;
+                help = "write it using `{{!plural \"{}\", ...}}`, so its form is picked for the locale", key;
+                note = "the form depends on the argument `{}`", selector,
+            ),
+            (None, true) => emit_error!(
+                self.key, "message `{}` has no plural forms", key;
+                help = "write it using `{{!i18n \"{}\", ...}}`", key;
+            ),
+            _ => (),
+        }
+        let placeholders = message.placeholders();
+        for p in &placeholders {
+            if !self.args.iter().any(|(n, _)| n == p) {
+                emit_error!(
+                    self.key, "missing argument `{}` for message `{}`", p, key;
+                    help = "pass it after the key as `{} = value`", p;
+                );
+            }
+        }
+        for (name, _) in &self.args {
+            if !placeholders.iter().any(|p| name == p) {
+                emit_error!(name, "message `{}` has no placeholder `{}`", key, name);
+            }
+        }
+    }
+}
+
+impl ToTokens for Message {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let key = &self.key;
+        let names = self
+            .args
+            .iter()
+            .map(|(n, _)| syn::LitStr::new(&n.to_string(), n.span()));
+        let values = self.args.iter().map(|(_, v)| v);
+        let checked = self
+            .checked
+            .as_ref()
+            .map(|path| quote! { const _: &[u8] = include_bytes!(#path); });
+        let delim = if self.quoted {
+            quote! { w.write_all(b"\"")?; }
+        } else {
+            quote! {}
+        };
+        let args = quote! { &[#((#names, &(#values) as &dyn ::std::fmt::Display)),*] };
+        // the implicit names get the span of the key, so an error for a
+        // missing one points at it.
+        let i18n = syn::Ident::new("i18n", key.span());
+        let write = if self.plural {
+            let locale = syn::Ident::new("locale", key.span());
+            quote! { #i18n.write_plural(w, #key, &#locale, #args)?; }
+        } else {
+            quote! { #i18n.write_message(w, #key, #args)?; }
+        };
+        quote! {
+            {
+                #checked
+                use ::qtpl::i18n::Catalog as _;
+                #delim
+                #write
+                #delim
+            }
+        }
+        .to_tokens(tokens);
+    }
+}
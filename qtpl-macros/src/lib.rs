@@ -1,59 +1,208 @@
+mod call;
+mod closure;
+mod config;
+mod directive;
+mod filter;
+mod html;
+mod i18n;
+mod locale;
+mod minify;
 mod tpl;
 
 use proc_macro::TokenStream;
-use proc_macro_error::proc_macro_error;
-use quote::quote;
+use proc_macro2::TokenTree;
+use proc_macro_error::{emit_error, proc_macro_error};
+use quote::{format_ident, quote};
 use syn::parse_macro_input;
+use syn::visit_mut::VisitMut;
+
+// Marks the input of a `tpl!` that `tplfn` has already expanded.
+const EXPANDED: &str = "__qtpl_expanded";
+
+// The buffer the output is rendered to, allocated up front for the size hint
+// if it's given, and otherwise for that of the template function it's passed
+// to.
+fn buffer(r: &call::Render) -> proc_macro2::TokenStream {
+    match &r.size_hint {
+        Some(hint) => quote! { ::qtpl::Buffer::with_capacity(#hint) },
+        None => quote! { ::qtpl::Buffer::new() },
+    }
+}
+
+// Renders the template to a `String`, evaluating to a `Result` with the error
+// type of the template.
+fn try_render_string_tokens(r: call::Render) -> proc_macro2::TokenStream {
+    let buffer = buffer(&r);
+    let c = call::with_writer(&r.template, syn::parse_quote!(&mut w));
+    quote! {
+        {
+            let mut w = #buffer;
+            match #c {
+                ::std::result::Result::Ok(()) => ::qtpl::__into_string(w.into_inner()),
+                ::std::result::Result::Err(err) => ::std::result::Result::Err(err),
+            }
+        }
+    }
+}
 
 #[proc_macro]
 #[proc_macro_error]
 pub fn render_string(input: TokenStream) -> TokenStream {
-    let mut c = parse_macro_input!(input as syn::ExprCall);
-    let arg: syn::Expr = syn::parse_quote!(&mut w);
-    c.args.insert(0, arg);
-    TokenStream::from(quote! {
-        {
-            let mut w = Vec::new();
-            #c.unwrap();
-            String::from_utf8(w).unwrap()
-        }
-    })
+    let r = parse_macro_input!(input as call::Render);
+    let s = try_render_string_tokens(r);
+    TokenStream::from(quote! { #s.unwrap() })
+}
+
+#[proc_macro]
+#[proc_macro_error]
+pub fn try_render_string(input: TokenStream) -> TokenStream {
+    let r = parse_macro_input!(input as call::Render);
+    TokenStream::from(try_render_string_tokens(r))
 }
 
 #[proc_macro]
 #[proc_macro_error]
 pub fn tpl(input: TokenStream) -> TokenStream {
+    // templates inside a `tplfn` have already been expanded by it.
+    let mut tokens = proc_macro2::TokenStream::from(input.clone()).into_iter();
+    if let (Some(TokenTree::Punct(p)), Some(TokenTree::Ident(i))) = (tokens.next(), tokens.next()) {
+        if p.as_char() == '@' && i == EXPANDED {
+            return TokenStream::from(tokens.collect::<proc_macro2::TokenStream>());
+        }
+    }
     let el = parse_macro_input!(input as tpl::Template);
     let result = quote! { #el };
     TokenStream::from(result)
 }
 
+#[proc_macro]
+#[proc_macro_error]
+pub fn tpl_closure(input: TokenStream) -> TokenStream {
+    let c = parse_macro_input!(input as closure::Closure);
+    TokenStream::from(quote! { #c })
+}
+
 #[proc_macro_attribute]
 #[proc_macro_error]
-pub fn tplfn(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn tplfn(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut options = parse_macro_input!(attr as syn::AttributeArgs);
     let mut f = parse_macro_input!(item as syn::ItemFn);
+    // the size hint constant is only defined when asked for, since it can't
+    // be defined in trait implementations, and `tplfn` can't tell if it's in
+    // one.
+    let size_hint = options.iter().position(
+        |o| matches!(o, syn::NestedMeta::Meta(syn::Meta::Path(p)) if p.is_ident("size_hint")),
+    );
+    let size_hint = size_hint.map(|i| options.remove(i));
+    // for methods the writer goes after `self`, so it's still the first
+    // argument in calls.
+    let method = matches!(f.sig.inputs.first(), Some(syn::FnArg::Receiver(_)));
     let arg: syn::FnArg = syn::parse_quote!(w: &mut dyn ::std::io::Write);
-    f.sig.inputs.insert(0, arg);
-    match f.sig.output {
-        syn::ReturnType::Default => {
-            f.sig.output = syn::parse_quote!(-> ::std::result::Result<(), ::std::io::Error>);
+    f.sig.inputs.insert(method as usize, arg);
+    if let syn::ReturnType::Default = f.sig.output {
+        f.sig.output = syn::parse_quote!(-> ::std::result::Result<(), ::std::io::Error>);
+    }
+    // if a template has an error, the function is still defined so its callers
+    // don't report errors of their own.
+    let mut dummy = f.clone();
+    StubTemplates.visit_block_mut(&mut dummy.block);
+    proc_macro_error::set_dummy(quote! { #[allow(unused)] #dummy });
+    // options are passed on to the templates as inner attributes, so they
+    // come before any the template specifies itself.
+    let mut expand = ExpandTemplates {
+        options: quote! { #(#![#options])* },
+        size_hint: 0,
+    };
+    expand.visit_block_mut(&mut f.block);
+    // the hint is recorded as the function starts, for the buffer of the
+    // rendering macros.
+    let hint = expand.size_hint;
+    if hint > 0 {
+        f.block
+            .stmts
+            .insert(0, syn::parse_quote!(::qtpl::__size_hint(#hint);));
+    }
+    match size_hint {
+        None => return TokenStream::from(quote!(#f)),
+        Some(option) if method => {
+            emit_error!(
+                option, "`size_hint` can't be used for methods";
+                help = "method calls can't find constants, so it's only for functions",
+            );
+            return TokenStream::from(quote!(#f));
+        }
+        Some(_) => (),
+    }
+    let (vis, name) = (&f.vis, &f.sig.ident);
+    let hint_name = call::const_ident("", name, "SIZE_HINT");
+    let doc = format!(
+        "The expected size in bytes of the output of `{}`, for allocating \
+         buffers up front.",
+        name
+    );
+    TokenStream::from(quote! {
+        #[doc = #doc]
+        #[allow(dead_code)]
+        #vis const #hint_name: usize = #hint;
+        #f
+    })
+}
+
+// Expands the `tpl!` invocations inside a `tplfn`, so the size of their output
+// is known, and passes on the options given to `tplfn`. The expanded code is
+// marked so `tpl!` writes it out as is.
+struct ExpandTemplates {
+    options: proc_macro2::TokenStream,
+    // the largest of the templates, since usually only one of them runs.
+    size_hint: usize,
+}
+
+impl VisitMut for ExpandTemplates {
+    // nested functions are separate templates.
+    fn visit_item_fn_mut(&mut self, _: &mut syn::ItemFn) {}
+
+    fn visit_macro_mut(&mut self, m: &mut syn::Macro) {
+        if m.path.segments.last().is_some_and(|s| s.ident == "tpl") {
+            let (options, tokens) = (&self.options, &m.tokens);
+            let expanded = match syn::parse2::<tpl::Template>(quote! { #options #tokens }) {
+                Ok(t) => {
+                    self.size_hint = self.size_hint.max(t.size_hint());
+                    quote! { #t }
+                }
+                Err(err) => err.to_compile_error(),
+            };
+            let marker = format_ident!("{}", EXPANDED);
+            m.tokens = quote! { @#marker #expanded };
+        }
+    }
+}
+
+// Replaces the `tpl!` invocations inside a `tplfn` with ones that don't write
+// anything, for when expanding them failed.
+struct StubTemplates;
+
+impl VisitMut for StubTemplates {
+    fn visit_item_fn_mut(&mut self, _: &mut syn::ItemFn) {}
+
+    fn visit_macro_mut(&mut self, m: &mut syn::Macro) {
+        if m.path.segments.last().is_some_and(|s| s.ident == "tpl") {
+            let marker = format_ident!("{}", EXPANDED);
+            m.tokens = quote! { @#marker ::std::result::Result::Ok(()) };
         }
-        _ => (),
     }
-    TokenStream::from(quote!(#f))
 }
 
 #[proc_macro]
 #[proc_macro_error]
 pub fn render(input: TokenStream) -> TokenStream {
-    let mut c = parse_macro_input!(input as syn::ExprCall);
-    let arg: syn::Expr = syn::parse_quote!(&mut w);
-    c.args.insert(0, arg);
+    let r = parse_macro_input!(input as call::Render);
+    let buffer = buffer(&r);
+    let c = call::with_writer(&r.template, syn::parse_quote!(&mut w));
     TokenStream::from(quote! {
         {
-            let mut w = vec![];
+            let mut w = #buffer;
             #c?;
-            w
+            w.into_inner()
         }
     })
 }
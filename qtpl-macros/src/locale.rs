@@ -0,0 +1,189 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::emit_error;
+use quote::{quote, ToTokens};
+use syn::parse::{ParseStream, Result};
+
+// The plural categories of CLDR, as used in `{!plural n, one = "..."}`.
+const CATEGORIES: &[(&str, &str)] = &[
+    ("zero", "Zero"),
+    ("one", "One"),
+    ("two", "Two"),
+    ("few", "Few"),
+    ("many", "Many"),
+    ("other", "Other"),
+];
+
+// A value formatted for the locale named `locale` in scope, or the one given
+// as `locale = expr` after the other arguments. Inside a tag it's written as a
+// quoted attribute value.
+pub struct Format {
+    kind: Kind,
+    locale: syn::Expr,
+    pub quoted: bool,
+}
+
+enum Kind {
+    Number(syn::Expr),
+    Currency(syn::Expr, Box<syn::Expr>),
+    Date(syn::Expr, syn::Ident),
+    Plural(syn::Expr, Vec<(syn::Ident, syn::Expr)>),
+}
+
+impl Format {
+    // Parses the rest of the block after the name of the directive, which is
+    // one of `number`, `currency`, `date` or `plural`.
+    pub fn parse(name: &syn::Ident, input: ParseStream) -> Result<Self> {
+        let value = input.parse()?;
+        let mut locale = None;
+        let kind = if name == "number" {
+            Kind::Number(value)
+        } else if name == "currency" {
+            input.parse::<syn::Token![,]>()?;
+            let code: syn::Expr = input.parse()?;
+            if let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(s),
+                ..
+            }) = &code
+            {
+                let v = s.value();
+                if v.len() != 3 || !v.bytes().all(|b| b.is_ascii_uppercase()) {
+                    emit_error!(
+                        s, "invalid currency code `{}`", v;
+                        help = "use the ISO 4217 code, such as `EUR`",
+                    );
+                }
+            }
+            Kind::Currency(value, Box::new(code))
+        } else if name == "date" {
+            let style = if input.is_empty() || peek_locale(input) {
+                syn::Ident::new("short", name.span())
+            } else {
+                input.parse::<syn::Token![,]>()?;
+                input.parse()?
+            };
+            if style != "short" && style != "long" {
+                emit_error!(style, "invalid date style, expected `short` or `long`");
+            }
+            Kind::Date(value, style)
+        } else {
+            let mut forms: Vec<(syn::Ident, syn::Expr)> = vec![];
+            while !input.is_empty() {
+                input.parse::<syn::Token![,]>()?;
+                if input.is_empty() {
+                    break;
+                }
+                if is_locale(input) {
+                    locale = Some(parse_locale(input)?);
+                    continue;
+                }
+                let category: syn::Ident = input.parse()?;
+                input.parse::<syn::Token![=]>()?;
+                let form = input.parse()?;
+                if !CATEGORIES.iter().any(|(c, _)| category == c) {
+                    let valid = CATEGORIES.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+                    emit_error!(
+                        category, "unknown plural category `{}`", category;
+                        note = "valid categories are {}", valid.join(", "),
+                    );
+                } else if forms.iter().any(|(c, _)| *c == category) {
+                    emit_error!(category, "`{}` is given more than once", category);
+                }
+                forms.push((category, form));
+            }
+            if !forms.iter().any(|(c, _)| c == "other") {
+                emit_error!(
+                    name, "`!plural` needs an `other` form";
+                    help = "it's used for the categories without a form, as in `other = \"# items\"`",
+                );
+            }
+            Kind::Plural(value, forms)
+        };
+        if locale.is_none() && peek_locale(input) {
+            input.parse::<syn::Token![,]>()?;
+            locale = Some(parse_locale(input)?);
+        }
+        // the implicit name gets the span of the directive, so an error for a
+        // missing `locale` points at it.
+        let locale = locale.unwrap_or_else(|| {
+            let ident = syn::Ident::new("locale", name.span());
+            syn::parse_quote!(#ident)
+        });
+        Ok(Self {
+            kind,
+            locale,
+            quoted: false,
+        })
+    }
+}
+
+// Whether the input continues with `locale = ...`.
+fn is_locale(input: ParseStream) -> bool {
+    let fork = input.fork();
+    matches!(fork.parse::<syn::Ident>(), Ok(i) if i == "locale") && fork.peek(syn::Token![=])
+}
+
+// Whether the input continues with `, locale = ...`.
+fn peek_locale(input: ParseStream) -> bool {
+    let fork = input.fork();
+    fork.parse::<syn::Token![,]>().is_ok() && is_locale(&fork)
+}
+
+fn parse_locale(input: ParseStream) -> Result<syn::Expr> {
+    input.parse::<syn::Ident>()?;
+    input.parse::<syn::Token![=]>()?;
+    input.parse()
+}
+
+impl ToTokens for Format {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let locale = &self.locale;
+        let as_str = |e: &syn::Expr| quote! { ::std::convert::AsRef::<str>::as_ref(&(#e)) };
+        let formatted = match &self.kind {
+            Kind::Number(n) => quote! {
+                ::qtpl::locale::Locale::format_number(&(#locale), &(#n))
+            },
+            Kind::Currency(amount, code) => {
+                let code = as_str(code);
+                quote! {
+                    ::qtpl::locale::Locale::format_currency(&(#locale), &(#amount), #code)
+                }
+            }
+            Kind::Date(date, style) => {
+                let style = if style == "long" {
+                    quote! { Long }
+                } else {
+                    quote! { Short }
+                };
+                quote! {
+                    ::qtpl::locale::Locale::format_date(
+                        &(#locale),
+                        #date,
+                        ::qtpl::locale::DateStyle::#style,
+                    )
+                }
+            }
+            Kind::Plural(n, forms) => {
+                let forms = forms.iter().filter_map(|(category, form)| {
+                    let (_, variant) = CATEGORIES.iter().find(|(c, _)| category == c)?;
+                    let variant = syn::Ident::new(variant, category.span());
+                    let form = as_str(form);
+                    Some(quote! { (::qtpl::locale::Plural::#variant, #form) })
+                });
+                quote! {
+                    ::qtpl::locale::Locale::format_plural(&(#locale), &(#n), &[#(#forms),*])
+                }
+            }
+        };
+        let delim = if self.quoted {
+            quote! { w.write_all(b"\"")?; }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #delim
+            ::qtpl::escape_to(w, #formatted.as_bytes())?;
+            #delim
+        }
+        .to_tokens(tokens);
+    }
+}
@@ -0,0 +1,210 @@
+// Rules for minifying the static parts of templates. These are conservative,
+// and only make changes that don't affect how the document is understood.
+
+// Returns whether the closing tag of `tag` can be omitted when it's followed
+// by the given tag. `next` is the name of the following tag, prefixed with a
+// `/` if it's a closing tag. See
+// https://html.spec.whatwg.org/#optional-tags for the rules.
+pub fn omits_end(tag: &str, next: &str) -> bool {
+    let followers: &[&str] = match tag {
+        "li" => &["li", "/ul", "/ol", "/menu"],
+        "dt" => &["dt", "dd"],
+        "dd" => &["dt", "dd", "/dl", "/div"],
+        "td" | "th" => &["td", "th", "/tr"],
+        "tr" => &["tr", "/table", "/tbody", "/thead", "/tfoot"],
+        "thead" => &["tbody", "tfoot"],
+        "tbody" => &["tbody", "tfoot", "/table"],
+        "tfoot" => &["/table"],
+        "option" => &["option", "optgroup", "/select", "/optgroup", "/datalist"],
+        "optgroup" => &["optgroup", "/select"],
+        "p" => &[
+            "address",
+            "article",
+            "aside",
+            "blockquote",
+            "details",
+            "div",
+            "dl",
+            "fieldset",
+            "figcaption",
+            "figure",
+            "footer",
+            "form",
+            "h1",
+            "h2",
+            "h3",
+            "h4",
+            "h5",
+            "h6",
+            "header",
+            "hgroup",
+            "hr",
+            "main",
+            "menu",
+            "nav",
+            "ol",
+            "p",
+            "pre",
+            "section",
+            "table",
+            "ul",
+            "/article",
+            "/aside",
+            "/blockquote",
+            "/body",
+            "/div",
+            "/footer",
+            "/form",
+            "/header",
+            "/li",
+            "/main",
+            "/nav",
+            "/section",
+            "/td",
+            "/th",
+        ],
+        _ => &[],
+    };
+    followers.contains(&next)
+}
+
+// Minifies an attribute value written as a string literal, dropping the
+// quotes where they aren't needed. Values with escapes, and raw strings, are
+// left alone.
+pub fn attr_value(name: &str, literal: &str) -> Option<String> {
+    let value = literal.strip_prefix('"')?.strip_suffix('"')?;
+    if value.contains('\\') {
+        return None;
+    }
+    let value = if name.eq_ignore_ascii_case("style") {
+        css(value).trim_end_matches(';').to_owned()
+    } else {
+        value.to_owned()
+    };
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || "\"'=<>`".contains(c));
+    Some(if needs_quotes {
+        format!("\"{}\"", value)
+    } else {
+        value
+    })
+}
+
+// Minifies a stylesheet by removing comments, and whitespace where it has no
+// meaning.
+pub fn css(src: &str) -> String {
+    // no space is needed around these, except before a `:` in a selector,
+    // which would turn `a :hover` into `a:hover`.
+    let tight = |c: char| "{};,>:".contains(c);
+    let mut out = String::new();
+    let mut chars = src.chars().peekable();
+    let mut space = false;
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        if c == '/' && chars.peek() == Some(&'*') {
+            chars.next();
+            let mut prev = ' ';
+            for c in chars.by_ref() {
+                if prev == '*' && c == '/' {
+                    break;
+                }
+                prev = c;
+            }
+            space = true;
+            continue;
+        }
+        let in_selector = || chars.clone().find(|c| "{;}".contains(*c)) == Some('{');
+        let spaced = !tight(c) || (c == ':' && in_selector());
+        if space && spaced && !out.ends_with(tight) && !out.is_empty() {
+            out.push(' ');
+        }
+        space = false;
+        if c == '}' && out.ends_with(';') {
+            out.pop();
+        }
+        out.push(c);
+        if c == '"' || c == '\'' {
+            let mut escaped = false;
+            for s in chars.by_ref() {
+                out.push(s);
+                if s == c && !escaped {
+                    break;
+                }
+                escaped = s == '\\' && !escaped;
+            }
+        }
+    }
+    out
+}
+
+// Minifies a script by removing blank lines. Anything else may be part of a
+// string, so lines are kept as written, as is a blank line continuing a
+// string ending in `\`. Scripts with template literals are left alone since
+// those may span lines.
+pub fn js(src: &str) -> String {
+    if src.contains('`') {
+        return src.to_owned();
+    }
+    let mut lines: Vec<&str> = vec![];
+    for line in src.lines() {
+        let continued = lines.last().is_some_and(|l| l.ends_with('\\'));
+        if continued || !line.trim().is_empty() {
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn minify_css() {
+        assert_eq!(
+            css("a :hover , b > i {\n  color : red ;\n  /* x */ content: ' a  b' ;\n}"),
+            "a :hover,b>i{color:red;content:' a  b'}",
+        );
+    }
+
+    #[test]
+    fn minify_attr_value() {
+        assert_eq!(attr_value("id", r#""a""#).as_deref(), Some("a"));
+        assert_eq!(attr_value("id", r#""a b""#).as_deref(), Some(r#""a b""#));
+        assert_eq!(attr_value("id", r#""""#).as_deref(), Some(r#""""#));
+        assert_eq!(
+            attr_value("style", r#""color : red ;""#).as_deref(),
+            Some("color:red")
+        );
+        assert_eq!(attr_value("id", r#""a\"b""#), None);
+        assert_eq!(attr_value("id", r##"r#"a"#"##), None);
+    }
+
+    #[test]
+    fn minify_js() {
+        assert_eq!(js("\n  let a = 1\n\n  f( a )\n  "), "  let a = 1\n  f( a )");
+        assert_eq!(js(" `a\n  b` "), " `a\n  b` ");
+        assert_eq!(
+            js("let a = 'x \\\n   \\\n\n  y'\n\nf(a)"),
+            "let a = 'x \\\n   \\\n\n  y'\nf(a)"
+        );
+    }
+
+    #[test]
+    fn optional_end_tags() {
+        assert!(omits_end("li", "li"));
+        assert!(omits_end("thead", "tbody"));
+        assert!(!omits_end("thead", "/table"));
+        assert!(omits_end("tbody", "/table"));
+        assert!(omits_end("dt", "dd"));
+        assert!(!omits_end("dt", "/dl"));
+        assert!(!omits_end("dt", "/div"));
+        assert!(omits_end("dd", "/dl"));
+    }
+}
@@ -1,9 +1,18 @@
+use crate::call;
+use crate::config::Config;
+use crate::directive;
+use crate::filter;
+use crate::html::{is_void, lint_attribute, lint_element, tag_name, TagStack};
+use crate::i18n;
+use crate::locale;
+use crate::minify;
 use proc_macro2::{Span, TokenStream};
-use proc_macro_error::emit_error;
+use proc_macro_error::{abort, emit_error};
 use quote::{quote, ToTokens};
 use syn::parse::{Parse, ParseStream, Result};
 use syn::spanned::Spanned;
 
+// Elements where whitespace around them and just inside them is insignificant.
 const WHITESPACE_INSENSITIVE: &[&str] = &[
     "!doctype",
     "address",
@@ -58,21 +67,26 @@ const WHITESPACE_INSENSITIVE: &[&str] = &[
     "ul",
 ];
 
-// GIANT HACK until the span start/end methods are available in stable
+// Elements whose contents are kept exactly as written.
+const WHITESPACE_PRESERVED: &[&str] = &["pre", "script", "style", "textarea"];
+
+// Source position of a token as (line, column) pairs. This is used to
+// reconstruct the whitespace between tokens, which the tokenizer discards.
+// Positions are only available on Rust 1.88+ inside the compiler, and are
+// meaningless for tokens that were themselves generated by a macro, so every
+// use must be prepared for them to be unknown.
 #[derive(Default, Copy, Clone)]
 struct SpanPos {
-    start: usize,
-    end: usize,
+    start: (usize, usize),
+    end: (usize, usize),
 }
 
 impl From<Span> for SpanPos {
     fn from(span: Span) -> Self {
-        let d = format!("{:?}", span);
-        let s = d.find('(').unwrap();
-        let e = d.find('.').unwrap();
+        let (start, end) = (span.start(), span.end());
         Self {
-            start: d[s + 1..e].parse().unwrap(),
-            end: d[e + 2..d.len() - 1].parse().unwrap(),
+            start: (start.line, start.column),
+            end: (end.line, end.column),
         }
     }
 }
@@ -81,18 +95,148 @@ impl SpanPos {
     fn move_end(&mut self, end: impl Into<SpanPos>) {
         self.end = end.into().end;
     }
+
+    // Real tokens always cover at least one character, so an empty position
+    // means the compiler didn't tell us where the token is.
+    fn is_known(&self) -> bool {
+        self.start < self.end
+    }
+
+    // Returns the whitespace between this and `next` as written in the source,
+    // or `None` if that can't be determined from the positions. Tabs are
+    // counted as a single column, and so come back as a single space.
+    fn gap(&self, next: SpanPos) -> Option<String> {
+        self.touches(next)?;
+        let ((line, col), (next_line, next_col)) = (self.end, next.start);
+        Some(if line == next_line {
+            " ".repeat(next_col - col)
+        } else {
+            "\n".repeat(next_line - line) + &" ".repeat(next_col)
+        })
+    }
+
+    // Returns whether `next` starts exactly where this ends, or `None` if
+    // that can't be determined from the positions. Positions that overlap or
+    // go backwards happen with macro generated input where many tokens share
+    // a span, and are treated as unknown.
+    fn touches(&self, next: SpanPos) -> Option<bool> {
+        if self.is_known() && next.is_known() && self.end <= next.start {
+            Some(self.end == next.start)
+        } else {
+            None
+        }
+    }
+}
+
+// The text of the file a template is in, used to recover whitespace exactly
+// as written where it's significant, including tabs and the whitespace inside
+// groups like `(a,  b)` which the tokenizer discards.
+struct Source {
+    lines: Vec<Vec<char>>,
+}
+
+impl Source {
+    fn load(span: Span) -> Option<Self> {
+        let text = std::fs::read_to_string(span.local_file()?).ok()?;
+        Some(Self {
+            lines: text.split('\n').map(|l| l.chars().collect()).collect(),
+        })
+    }
+
+    // Returns the whitespace between the positions, or `None` if there's
+    // anything else there, which means the positions aren't from this file.
+    fn between(&self, start: (usize, usize), end: (usize, usize)) -> Option<String> {
+        let text = self.text(start, end)?;
+        if text.chars().all(char::is_whitespace) {
+            Some(text)
+        } else {
+            None
+        }
+    }
+
+    fn text(&self, start: (usize, usize), end: (usize, usize)) -> Option<String> {
+        let line = |n: usize| n.checked_sub(1).and_then(|n| self.lines.get(n));
+        let mut text: String;
+        if start.0 == end.0 {
+            text = line(start.0)?.get(start.1..end.1)?.iter().collect();
+        } else {
+            text = line(start.0)?.get(start.1..)?.iter().collect();
+            for n in start.0 + 1..end.0 {
+                text.push('\n');
+                text.extend(line(n)?);
+            }
+            text.push('\n');
+            text.extend(line(end.0)?.get(..end.1)?);
+        }
+        Some(text)
+    }
+}
+
+// Returns the source text of a token if it matches the token, which keeps the
+// whitespace inside groups.
+fn source_text(span: Span, token: &str) -> Option<String> {
+    matching(span.source_text()?, token)
+}
+
+// Returns the text if it only differs from `tokens` in whitespace.
+fn matching(text: String, tokens: &str) -> Option<String> {
+    let compact = |s: &str| s.split_whitespace().collect::<String>();
+    if compact(&text) == compact(tokens) {
+        Some(text)
+    } else {
+        None
+    }
 }
 
 enum Braced {
     Default(syn::Expr),
     Attribute(syn::Expr),
+    NamedAttribute(String, syn::Expr),
+    Spread(syn::Expr),
+    Whitespace(&'static str),
+    Comment,
     Content(syn::Expr),
     Bytes(syn::Expr),
-    TplFn(syn::ExprCall),
+    TplFn(syn::Expr),
+    Directive(syn::Ident, syn::Expr),
+    Custom(syn::Path, syn::Expr, Vec<syn::Expr>),
+    I18n(i18n::Message),
+    Locale(Box<locale::Format>),
 }
 
 impl Parse for Braced {
     fn parse(input: ParseStream) -> Result<Self> {
+        if input.peek(syn::Token![#]) {
+            let tokens: TokenStream = input.fork().parse()?;
+            let last = tokens.into_iter().last().map(|tt| tt.to_string());
+            if last.as_deref() == Some("#") {
+                input.parse::<TokenStream>()?;
+                return Ok(Self::Comment);
+            }
+        }
+        let fork = input.fork();
+        if fork.parse::<syn::Token![-]>().is_ok() && fork.is_empty() {
+            input.parse::<syn::Token![-]>()?;
+            return Ok(Self::Whitespace(""));
+        }
+        if input.peek(syn::Token![~]) {
+            input.parse::<syn::Token![~]>()?;
+            if input.is_empty() {
+                return Ok(Self::Whitespace(" "));
+            }
+            let n: syn::Ident = input.parse()?;
+            if n != "n" {
+                emit_error!(
+                    n.span(),
+                    "invalid whitespace directive, expected `~` or `~n`"
+                );
+            }
+            return Ok(Self::Whitespace("\n"));
+        }
+        if input.peek(syn::Token![..]) {
+            input.parse::<syn::Token![..]>()?;
+            return Ok(Self::Spread(input.parse()?));
+        }
         let fp: Result<syn::Token![!]> = input.parse();
         if fp.is_ok() {
             let modifier: syn::Ident = input.parse()?;
@@ -101,10 +245,17 @@ impl Parse for Braced {
                 "a" => Ok(Self::Attribute(input.parse()?)),
                 "b" => Ok(Self::Bytes(input.parse()?)),
                 "t" => Ok(Self::TplFn(input.parse()?)),
-                _ => {
-                    emit_error!(modifier.span(), "invalid formatting directive: {}", &ms);
-                    Ok(Self::Default(input.parse()?))
+                "i18n" => Ok(Self::I18n(input.parse()?)),
+                // with a key, the forms come from the catalog.
+                "plural" if input.peek(syn::LitStr) => {
+                    let mut message: i18n::Message = input.parse()?;
+                    message.plural = true;
+                    Ok(Self::I18n(message))
                 }
+                "number" | "currency" | "date" | "plural" => Ok(Self::Locale(Box::new(
+                    locale::Format::parse(&modifier, input)?,
+                ))),
+                _ => Ok(Self::Directive(modifier, input.parse()?)),
             }
         } else {
             Ok(Self::Default(input.parse()?))
@@ -112,19 +263,128 @@ impl Parse for Braced {
     }
 }
 
+impl Braced {
+    // Resolves directives the user defined, and filter chains like
+    // `{value | f | g(x)}`. Built in filters transform the value, which is then
+    // written as usual, while the last filter may instead be a directive, or the
+    // path to a function which writes it if it has more than one segment or
+    // takes arguments. Bitwise or is still available by wrapping the
+    // expression in parens. Messages are checked against the default catalog.
+    fn resolve(&mut self, options: &Options) {
+        let custom = &options.directives;
+        let lookup = |name: &syn::Ident| {
+            custom
+                .iter()
+                .find(|(n, _)| name == n)
+                .map(|(_, p)| p.clone())
+        };
+        match self {
+            Self::Directive(name, e) => match lookup(name) {
+                Some(path) => *self = Self::Custom(path, e.clone(), vec![]),
+                None => directive::unknown(name, custom),
+            },
+            Self::Default(e) => {
+                let (mut value, filters) = match filter::chain(e) {
+                    Some(chain) => chain,
+                    None => return,
+                };
+                let last = filters.len() - 1;
+                for (i, f) in filters.into_iter().enumerate() {
+                    // directives take precedence over built in filters.
+                    let directive = f.path.get_ident().and_then(lookup);
+                    match f.builtin() {
+                        Some(name) if directive.is_none() => value = f.apply(name, value),
+                        _ if i != last => return filter::not_last(&f),
+                        // a lone name is only a function if it's a directive.
+                        _ if directive.is_none()
+                            && f.args.is_empty()
+                            && f.path.get_ident().is_some() =>
+                        {
+                            return filter::unknown(&f)
+                        }
+                        _ => {
+                            let path = directive.unwrap_or(f.path);
+                            *self = Self::Custom(path, value, f.args);
+                            return;
+                        }
+                    }
+                }
+                *self = Self::Default(value);
+            }
+            Self::I18n(m) => m.check(options.i18n_catalog.as_ref()),
+            _ => (),
+        }
+    }
+}
+
 impl ToTokens for Braced {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         match self {
-            Self::Default(_) => panic!("Default should have been transformed!"),
-            Self::Attribute(b) => quote! { write!(w, "\"{}\"", ::qtpl::escape(#b.as_ref()))?; },
+            Self::Default(e) => abort!(
+                e, "block was not resolved to content or an attribute";
+                note = "this is a bug in qtpl",
+            ),
+            // these are applied while parsing, and write nothing themselves.
+            Self::Whitespace(_) | Self::Comment => quote! {},
+            // binding the value to a `&str` allows for deref coercion, as in
+            // `Rc<String>`, and borrowing it first keeps temporaries alive.
+            Self::Attribute(b) => quote! {
+                {
+                    let v = &(#b);
+                    let s: &str = v.as_ref();
+                    w.write_all(b"\"")?;
+                    ::qtpl::escape_to(w, s.as_bytes())?;
+                    w.write_all(b"\"")?;
+                }
+            },
+            Self::NamedAttribute(name, b) => {
+                let name = syn::LitByteStr::new(name.as_bytes(), Span::call_site());
+                let value = match b {
+                    syn::Expr::Array(a) => {
+                        let classes = a.elems.iter().map(|e| match e {
+                            syn::Expr::Tuple(t) if t.elems.len() == 2 => {
+                                let (class, cond) = (&t.elems[0], &t.elems[1]);
+                                quote! {
+                                    if #cond {
+                                        Some(::std::convert::AsRef::<str>::as_ref(&#class))
+                                    } else {
+                                        None
+                                    }
+                                }
+                            }
+                            _ => quote! { Some(::std::convert::AsRef::<str>::as_ref(&#e)) },
+                        });
+                        quote! { &::qtpl::Classes(&[#(#classes),*]) }
+                    }
+                    _ => quote! { &(#b) },
+                };
+                quote! {
+                    {
+                        use ::qtpl::{WriteAttrStr as _, WriteAttrValue as _};
+                        (&::qtpl::Attr(#value)).write_attr(w, #name)?;
+                    }
+                }
+            }
+            Self::Spread(b) => quote! { ::qtpl::write_attrs(w, #b)?; },
             Self::Bytes(b) => quote! { w.write_all(#b)?; },
-            Self::Content(b) => quote! { write!(w, "{}", ::qtpl::escape(#b.as_ref()))?; },
+            Self::Directive(name, _) => abort!(
+                name, "directive was not resolved";
+                note = "this is a bug in qtpl",
+            ),
+            Self::Custom(path, b, args) => quote! { #path(w, &(#b), #(#args),*)?; },
+            Self::Content(b) => quote! {
+                {
+                    let v = &(#b);
+                    let s: &str = v.as_ref();
+                    ::qtpl::escape_to(w, s.as_bytes())?;
+                }
+            },
             Self::TplFn(b) => {
-                let mut c = b.clone();
-                let arg: syn::Expr = syn::parse_quote!(w);
-                c.args.insert(0, arg);
+                let c = call::with_writer(b, syn::parse_quote!(w));
                 quote! { #c?; }
             }
+            Self::I18n(m) => m.to_token_stream(),
+            Self::Locale(f) => f.to_token_stream(),
         }
         .to_tokens(tokens);
     }
@@ -132,6 +392,7 @@ impl ToTokens for Braced {
 
 struct Name {
     value: String,
+    span: Span,
     span_pos: SpanPos,
 }
 
@@ -139,21 +400,30 @@ impl Parse for Name {
     fn parse(input: ParseStream) -> Result<Self> {
         input.step(|cursor| {
             let mut value = String::new();
+            let mut span = cursor.span();
             let mut span_pos = SpanPos::default();
             let mut rest = *cursor;
             let mut first = true;
             while let Some((tt, next)) = rest.token_tree() {
                 let tts = tt.to_string();
                 if first {
-                    span_pos = SpanPos::from(tt.span());
+                    span = tt.span();
+                    span_pos = SpanPos::from(span);
                     first = false;
                 } else {
                     let cur_span = SpanPos::from(tt.span());
-                    if span_pos.end != cur_span.start {
+                    let first = tts.chars().next().unwrap_or_default();
+                    if !first.is_alphabetic() && first != '/' && first != '-' {
                         break;
                     }
-                    let first = tts.chars().next().unwrap();
-                    if !first.is_alphabetic() && first != '/' {
+                    // Without positions, only join a word onto punctuation as
+                    // in `!doctype` or `my-element`, never two words as in
+                    // `!doctype html`.
+                    let touches = span_pos.touches(cur_span).unwrap_or_else(|| {
+                        let last = value.chars().last().unwrap_or_default();
+                        !first.is_alphabetic() || !last.is_alphanumeric()
+                    });
+                    if !touches {
                         break;
                     }
                     span_pos.move_end(cur_span);
@@ -161,38 +431,96 @@ impl Parse for Name {
                 value.push_str(&tts);
                 rest = next;
             }
-            Ok((Self { value, span_pos }, rest))
+            Ok((
+                Self {
+                    value,
+                    span,
+                    span_pos,
+                },
+                rest,
+            ))
         })
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 enum ItemElement {
     Literal(String),
     Braced(Braced),
     StartOpenTag(Name),
     StartCloseTag(Name),
     EndTag,
+    Comment(String),
+}
+
+impl ItemElement {
+    // Whether the item begins like a word. Used together with `ends_word` to
+    // guess the spacing between items when source positions are unknown.
+    fn starts_word(&self) -> bool {
+        match self {
+            Self::Literal(l) => l
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphanumeric() || "_(\"'&#".contains(c)),
+            Self::Braced(_) => true,
+            Self::StartOpenTag(_) | Self::StartCloseTag(_) | Self::EndTag | Self::Comment(_) => {
+                false
+            }
+        }
+    }
+
+    // Whether the item ends like a word, including trailing punctuation which
+    // is usually followed by a space in text.
+    fn ends_word(&self) -> bool {
+        match self {
+            Self::Literal(l) => l
+                .chars()
+                .last()
+                .is_some_and(|c| c.is_alphanumeric() || "_)\"'.,;:!?".contains(c)),
+            Self::Braced(_) | Self::StartOpenTag(_) => true,
+            Self::StartCloseTag(_) | Self::EndTag | Self::Comment(_) => false,
+        }
+    }
 }
 
 struct Item {
     element: ItemElement,
+    span: Span,
     span_pos: SpanPos,
 }
 
 impl Item {
-    fn new(span_pos: SpanPos, element: ItemElement) -> Self {
-        Item { span_pos, element }
+    fn new(span: Span, span_pos: SpanPos, element: ItemElement) -> Self {
+        Item {
+            span,
+            span_pos,
+            element,
+        }
     }
 
-    fn alter_braced_default(&mut self, in_open_tag: bool) {
-        if let ItemElement::Braced(b) = &self.element {
-            if let Braced::Default(e) = b {
-                let e = e.clone();
-                self.element = if in_open_tag {
-                    ItemElement::Braced(Braced::Attribute(e))
-                } else {
-                    ItemElement::Braced(Braced::Content(e))
-                }
+    // Picks the escaping for a block without a formatting directive based on
+    // where it appears. A block that is the value of a named attribute takes
+    // over writing the name from the `literal` preceding it, so the attribute
+    // can be omitted entirely based on the value. Messages and values
+    // formatted for the locale are quoted inside a tag.
+    fn alter_braced_default(&mut self, in_open_tag: bool, literal: &mut String) {
+        match &mut self.element {
+            ItemElement::Braced(Braced::I18n(m)) => m.quoted = in_open_tag,
+            ItemElement::Braced(Braced::Locale(f)) => f.quoted = in_open_tag,
+            _ => (),
+        }
+        if let ItemElement::Braced(Braced::Default(e)) = &self.element {
+            let e = e.clone();
+            self.element = if !in_open_tag {
+                ItemElement::Braced(Braced::Content(e))
+            } else if let Some(name) = literal.trim_end().strip_suffix('=') {
+                let name = name.trim_end();
+                let start = name.rfind(char::is_whitespace).unwrap_or(0);
+                let attr = format!(" {}", name[start..].trim_start());
+                literal.truncate(start);
+                ItemElement::Braced(Braced::NamedAttribute(attr, e))
+            } else {
+                ItemElement::Braced(Braced::Attribute(e))
             }
         }
     }
@@ -203,43 +531,97 @@ impl Parse for Item {
         // we get the starting position from the first span, and then we'll move
         // the end once we figure out where that is exactly.
         let mut span_pos = SpanPos::from(input.span());
+        // the span of the whole first token, as `input.span()` is only the
+        // opening delimiter for groups.
+        let span = match input.cursor().token_tree() {
+            Some((tt, _)) => tt.span(),
+            None => input.span(),
+        };
         if input.peek(syn::Token!(<)) {
             input.parse::<syn::Token!(<)>()?;
-            if input.peek(syn::Token!(/)) {
+            if input.peek(syn::Token!(!))
+                && input.peek2(syn::Token!(-))
+                && input.peek3(syn::Token!(-))
+            {
+                Self::parse_comment(input, span, span_pos)
+            } else if input.peek(syn::Token!(/)) {
                 input.parse::<syn::Token!(/)>()?;
                 let name = input.parse::<Name>()?;
                 span_pos.move_end(name.span_pos);
-                Ok(Self::new(span_pos, ItemElement::StartCloseTag(name)))
+                Ok(Self::new(span, span_pos, ItemElement::StartCloseTag(name)))
             } else {
                 let name = input.parse::<Name>()?;
                 span_pos.move_end(name.span_pos);
-                Ok(Self::new(span_pos, ItemElement::StartOpenTag(name)))
+                Ok(Self::new(span, span_pos, ItemElement::StartOpenTag(name)))
             }
         } else if input.peek(syn::Token!(>)) {
             let angle = input.parse::<syn::Token!(>)>()?;
             span_pos.move_end(angle.span());
-            Ok(Self::new(span_pos, ItemElement::EndTag))
+            Ok(Self::new(span, span_pos, ItemElement::EndTag))
         } else if input.peek(syn::token::Brace) {
             let content;
             let braced = syn::braced!(content in input);
             span_pos.move_end(braced.span);
-            Ok(Self::new(span_pos, ItemElement::Braced(content.parse()?)))
-        } else {
             Ok(Self::new(
+                span,
                 span_pos,
-                ItemElement::Literal(input.step(|cursor| {
-                    if let Some((tt, next)) = cursor.token_tree() {
-                        span_pos.move_end(tt.span());
-                        Ok((tt.to_string(), next))
-                    } else {
-                        panic!("unexpected internal error: was expecting some tokens");
-                    }
-                })?),
+                ItemElement::Braced(content.parse()?),
             ))
+        } else {
+            let literal = input.step(|cursor| {
+                if let Some((tt, next)) = cursor.token_tree() {
+                    span_pos.move_end(tt.span());
+                    Ok((tt.to_string(), next))
+                } else {
+                    Err(cursor.error("unexpected end of template"))
+                }
+            })?;
+            Ok(Self::new(span, span_pos, ItemElement::Literal(literal)))
         }
     }
 }
 
+impl Item {
+    // Parses the rest of a comment after the `<`, up to and including the
+    // `-->`. The comment is kept as a single item, since it may contain
+    // anything including tags.
+    fn parse_comment(input: ParseStream, span: Span, mut span_pos: SpanPos) -> Result<Self> {
+        input.parse::<syn::Token!(!)>()?;
+        input.parse::<syn::Token!(-)>()?;
+        let dash = input.parse::<syn::Token!(-)>()?;
+        let mut comment = String::from("<!--");
+        let mut prev_span_pos = SpanPos::from(dash.span());
+        input.step(|cursor| {
+            let mut rest = *cursor;
+            while let Some((tt, next)) = rest.token_tree() {
+                let cur_span_pos = SpanPos::from(tt.span());
+                let tts = tt.to_string();
+                // without positions, words are spaced apart, as is the `--`
+                // that ends the comment.
+                let touches = prev_span_pos.touches(cur_span_pos).unwrap_or_else(|| {
+                    let first = tts.chars().next().unwrap_or_default();
+                    !first.is_alphanumeric() && (first != '-' || comment.ends_with('-'))
+                });
+                if !touches {
+                    comment.push(' ');
+                }
+                prev_span_pos = cur_span_pos;
+                comment.push_str(&tts);
+                rest = next;
+                if comment.ends_with("-->") {
+                    span_pos.move_end(cur_span_pos);
+                    return Ok(((), rest));
+                }
+            }
+            Err(syn::Error::new(
+                span,
+                "comment is not closed, expected `-->`",
+            ))
+        })?;
+        Ok(Self::new(span, span_pos, ItemElement::Comment(comment)))
+    }
+}
+
 impl ToTokens for Item {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         match &self.element {
@@ -250,71 +632,490 @@ impl ToTokens for Item {
                 }
             }
             ItemElement::Braced(b) => quote! { #b },
-            _ => panic!("unexpected ToTokens for item besides Literal or Braced"),
+            _ => abort!(
+                self.span, "tag was not merged into the template text";
+                note = "this is a bug in qtpl",
+            ),
         }
         .to_tokens(tokens)
     }
 }
 
+// Trims whitespace from the end of the literal, but not past the `pinned`
+// length.
+fn trim_literal(literal: &mut String, pinned: usize) {
+    let len = literal.trim_end().len().max(pinned);
+    literal.truncate(len);
+}
+
+// Replaces whitespace at the end of the literal with a newline and
+// indentation, for the pretty whitespace policy. Nothing is added at the start
+// of the template or right after a whitespace directive.
+fn break_line(literal: &mut String, pinned: usize, started: bool, depth: usize) {
+    trim_literal(literal, pinned);
+    if (started || !literal.is_empty()) && (pinned == 0 || literal.len() > pinned) {
+        literal.push('\n');
+        literal.push_str(&"  ".repeat(depth));
+    }
+}
+
+// Drops a closing tag at the end of the literal for the minify option, if
+// it's made redundant by the `next` tag. Only closing tags that nothing but
+// whitespace has been written after are considered.
+fn omit_end_tag(literal: &mut String, end_tag: Option<(String, usize, usize)>, next: &str) {
+    if let Some((tag, start, end)) = end_tag {
+        if literal.trim_end().len() == end && minify::omits_end(&tag, next) {
+            literal.truncate(start);
+        }
+    }
+}
+
+// Collects attribute names from the items inside an opening tag, since the
+// tokenizer splits names like `data-id` or `xml:lang` into many tokens.
+#[derive(Default)]
+struct AttrName {
+    name: String,
+    span: Option<Span>,
+    span_pos: SpanPos,
+    in_value: bool,
+}
+
+impl AttrName {
+    // Feeds the next item inside an opening tag, returning the attribute name
+    // it completed, if any.
+    fn push(&mut self, item: &Item, span: Span) -> Option<(String, Span)> {
+        match &item.element {
+            ItemElement::Literal(_) if self.in_value => {
+                self.in_value = false;
+                None
+            }
+            ItemElement::Literal(l) if l == "=" => {
+                self.in_value = true;
+                self.take()
+            }
+            ItemElement::Literal(l) if l != "/" => {
+                let joins = self.span.is_some()
+                    && self.span_pos.touches(item.span_pos).unwrap_or_else(|| {
+                        l == "-" || l == ":" || self.name.ends_with(&['-', ':'][..])
+                    });
+                let done = if joins { None } else { self.take() };
+                self.span.get_or_insert(span);
+                self.name.push_str(l);
+                self.span_pos = item.span_pos;
+                done
+            }
+            _ => {
+                self.in_value = false;
+                self.take()
+            }
+        }
+    }
+
+    fn take(&mut self) -> Option<(String, Span)> {
+        let span = self.span.take()?;
+        Some((std::mem::take(&mut self.name), span))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Whitespace {
+    Collapse,
+    Preserve,
+    Minify,
+    Pretty,
+}
+
+impl Whitespace {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "collapse" => Some(Self::Collapse),
+            "preserve" => Some(Self::Preserve),
+            "minify" => Some(Self::Minify),
+            "pretty" => Some(Self::Pretty),
+            _ => None,
+        }
+    }
+}
+
+// The default estimate of the bytes written by each block, for size hints.
+const BLOCK_SIZE_HINT: usize = 16;
+
+// Options are given as inner attributes at the start of the template, for
+// example `#![fragment]`. Defaults for the whitespace options, the estimate
+// for blocks and the default catalog for messages can be set for the whole
+// crate in `[package.metadata.qtpl]`.
+struct Options {
+    fragment: bool,
+    lint: bool,
+    // also implies the minify whitespace policy, and only applies as long as
+    // no other policy is given.
+    minify: bool,
+    whitespace: Whitespace,
+    whitespace_insensitive: Vec<String>,
+    directives: directive::Custom,
+    block_size_hint: usize,
+    i18n_catalog: Option<i18n::Catalog>,
+    manifest: Option<String>,
+}
+
+impl Options {
+    fn from_config(config: Config) -> Self {
+        let whitespace = match config.whitespace {
+            Some(v) => Whitespace::from_str(&v).unwrap_or_else(|| {
+                emit_error!(
+                    Span::call_site(),
+                    "invalid whitespace in [package.metadata.qtpl]: {}", v;
+                    help = "expected one of: collapse, preserve, minify, pretty",
+                );
+                Whitespace::Collapse
+            }),
+            // pretty wins when both features are enabled, as it's meant for
+            // debugging.
+            None if cfg!(feature = "pretty") => Whitespace::Pretty,
+            None if cfg!(feature = "minify") => Whitespace::Minify,
+            None => Whitespace::Collapse,
+        };
+        let whitespace_insensitive = config
+            .whitespace_insensitive
+            .map(|v| v.split_whitespace().map(tag_name).collect())
+            .unwrap_or_default();
+        Self {
+            fragment: false,
+            lint: false,
+            minify: cfg!(feature = "minify"),
+            whitespace,
+            whitespace_insensitive,
+            directives: vec![],
+            block_size_hint: config.block_size_hint.unwrap_or(BLOCK_SIZE_HINT),
+            i18n_catalog: config
+                .i18n_catalog
+                .map(|path| i18n::Catalog::new(path, Span::call_site())),
+            manifest: config.manifest,
+        }
+    }
+
+    // Whether whitespace before the start tag and after the end tag of the
+    // element is insignificant.
+    fn trims_around(&self, tag: &str) -> bool {
+        let tag = tag_name(tag);
+        match self.whitespace {
+            Whitespace::Preserve => false,
+            Whitespace::Minify => true,
+            Whitespace::Collapse | Whitespace::Pretty => {
+                WHITESPACE_INSENSITIVE.contains(&tag.as_str())
+                    || self.whitespace_insensitive.contains(&tag)
+            }
+        }
+    }
+
+    // Whether whitespace just inside the start and end tags of the element is
+    // insignificant.
+    fn trims_inside(&self, tag: &str) -> bool {
+        !WHITESPACE_PRESERVED.contains(&tag_name(tag).as_str()) && self.trims_around(tag)
+    }
+}
+
+impl Parse for Options {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut options = Self::from_config(Config::load());
+        for attr in input.call(syn::Attribute::parse_inner)? {
+            match attr.parse_meta()? {
+                syn::Meta::Path(p) if p.is_ident("fragment") => options.fragment = true,
+                syn::Meta::Path(p) if p.is_ident("lint") => options.lint = true,
+                syn::Meta::Path(p) if p.is_ident("minify") => {
+                    options.minify = true;
+                    options.whitespace = Whitespace::Minify;
+                }
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(s),
+                    ..
+                }) if path.is_ident("whitespace") => match Whitespace::from_str(&s.value()) {
+                    Some(w) => options.whitespace = w,
+                    None => emit_error!(
+                        s.span(),
+                        "invalid whitespace, expected one of: collapse, preserve, minify, pretty"
+                    ),
+                },
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(s),
+                    ..
+                }) if path.is_ident("whitespace_insensitive") => options
+                    .whitespace_insensitive
+                    .extend(s.value().split_whitespace().map(tag_name)),
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Int(i),
+                    ..
+                }) if path.is_ident("block_size_hint") => {
+                    options.block_size_hint = i.base10_parse()?
+                }
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    lit: syn::Lit::Str(s),
+                    ..
+                }) if path.is_ident("i18n_catalog") => {
+                    options.i18n_catalog = Some(i18n::Catalog::new(s.value(), s.span()))
+                }
+                syn::Meta::List(l) if l.path.is_ident("directive") => {
+                    directive::define(l, &mut options.directives)
+                }
+                _ => emit_error!(attr.path.span(), "invalid template option"),
+            }
+        }
+        options.minify &= options.whitespace == Whitespace::Minify;
+        Ok(options)
+    }
+}
+
 pub struct Template {
     items: Vec<Item>,
+    block_size_hint: usize,
+    manifest: Option<String>,
+}
+
+impl Template {
+    // The expected number of bytes written: the static text plus an estimate
+    // for each block.
+    pub fn size_hint(&self) -> usize {
+        self.items
+            .iter()
+            .map(|i| match &i.element {
+                ItemElement::Literal(l) => l.len(),
+                ItemElement::Braced(Braced::Whitespace(_) | Braced::Comment) => 0,
+                _ => self.block_size_hint,
+            })
+            .sum()
+    }
 }
 
 impl Parse for Template {
     fn parse(input: ParseStream) -> Result<Self> {
+        let options = input.parse::<Options>()?;
+        let mut tags = TagStack::new(options.fragment);
         let mut items = vec![];
         let mut literal = String::new();
+        let mut literal_start = Span::call_site();
         let mut literal_start_pos = SpanPos::default();
         let mut prev_span_pos = SpanPos::default();
+        let mut prev_ends_word = false;
         let mut skip_space = true;
+        // whether the next item starts on a new line when pretty printing.
+        let mut line_break = false;
+        // the length of the literal that came from whitespace directives,
+        // which must not be trimmed.
+        let mut pinned = 0;
         let mut current_tag = String::new();
         let mut in_open_tag = false;
+        // the element whose contents we're in, if they preserve whitespace.
+        let mut preserved = None;
+        // loaded the first time whitespace needs to be kept as written.
+        let mut source = None;
+        let mut attr_name = AttrName::default();
+        // for the minify option: the last closing tag which may turn out to be
+        // redundant as (name, start, end) in the literal, where an unquoted
+        // attribute value ends, and where the contents of a `<script>` or
+        // `<style>` start.
+        let mut end_tag = None;
+        let mut unquoted_end = None;
+        let mut raw_start = None;
         while !input.is_empty() {
             let mut item = Item::parse(input)?;
-            let span_pos = item.span_pos;
+            let (span, span_pos) = (item.span, item.span_pos);
+            let last_end_tag = end_tag.take();
+            let last_unquoted_end = unquoted_end.take();
+
+            if in_open_tag {
+                if let Some((name, span)) = attr_name.push(&item, span) {
+                    // `<!doctype html>` and the like have no attributes.
+                    if options.lint && !tags.in_foreign() && !current_tag.starts_with('!') {
+                        lint_attribute(&name, span);
+                    }
+                }
+            }
 
             if literal.is_empty() {
+                literal_start = span;
                 literal_start_pos = span_pos;
             }
 
-            if !skip_space && prev_span_pos.end != span_pos.start {
-                literal.push_str(" ");
+            let verbatim = preserved.is_some() || options.whitespace == Whitespace::Preserve;
+            if !skip_space {
+                match prev_span_pos.gap(span_pos) {
+                    Some(gap) if verbatim => {
+                        let exact = source
+                            .get_or_insert_with(|| Source::load(span))
+                            .as_ref()
+                            .and_then(|s| s.between(prev_span_pos.end, span_pos.start));
+                        literal.push_str(&exact.unwrap_or(gap));
+                    }
+                    Some(gap) if !gap.is_empty() => literal.push(' '),
+                    Some(_) => (),
+                    None if prev_ends_word && item.element.starts_word() => literal.push(' '),
+                    None => (),
+                }
+            }
+            if line_break {
+                break_line(&mut literal, pinned, !items.is_empty(), tags.depth());
+                line_break = false;
             }
             prev_span_pos = span_pos;
+            prev_ends_word = item.element.ends_word();
             skip_space = false;
 
             match item.element {
+                ItemElement::Literal(l) if options.minify && in_open_tag => {
+                    if last_unquoted_end == Some(literal.len()) {
+                        // the value would otherwise run into what follows.
+                        literal.push(' ');
+                    }
+                    let name = literal
+                        .strip_suffix('=')
+                        .and_then(|n| n.rsplit(char::is_whitespace).next());
+                    match name.and_then(|n| minify::attr_value(n, &l)) {
+                        Some(v) => {
+                            let unquoted = !v.starts_with('"');
+                            literal.push_str(&v);
+                            if unquoted {
+                                unquoted_end = Some(literal.len());
+                            }
+                        }
+                        None => literal.push_str(&l),
+                    }
+                }
+                ItemElement::Literal(l) if verbatim => {
+                    literal.push_str(&source_text(span, &l).unwrap_or(l))
+                }
                 ItemElement::Literal(l) => literal.push_str(&l),
-                ItemElement::Braced(_) => {
+                ItemElement::Braced(Braced::Whitespace(ws)) => {
+                    trim_literal(&mut literal, pinned);
+                    literal.push_str(ws);
+                    pinned = literal.len();
+                    skip_space = true;
+                }
+                ItemElement::Braced(Braced::Comment) => {
+                    skip_space = literal.is_empty() || literal.ends_with(char::is_whitespace);
+                }
+                ItemElement::Braced(ref b) => {
+                    if let Braced::Spread(_) = b {
+                        if !in_open_tag {
+                            emit_error!(span, "attributes can only be spread inside a tag");
+                        }
+                        // each spread attribute brings its own leading space
+                        trim_literal(&mut literal, pinned);
+                    }
+                    if let ItemElement::Braced(b) = &mut item.element {
+                        b.resolve(&options);
+                    }
+                    item.alter_braced_default(in_open_tag, &mut literal);
                     if !literal.is_empty() {
                         let mut span_pos = literal_start_pos;
                         span_pos.move_end(prev_span_pos);
-                        items.push(Item::new(span_pos, ItemElement::Literal(literal)));
+                        items.push(Item::new(
+                            literal_start,
+                            span_pos,
+                            ItemElement::Literal(literal),
+                        ));
                         literal = String::new();
+                        pinned = 0;
+                        raw_start = None;
                     }
-                    item.alter_braced_default(in_open_tag);
                     items.push(item);
                 }
+                ItemElement::Comment(c) => {
+                    if options.minify && preserved.is_none() {
+                        skip_space = literal.is_empty() || literal.ends_with(char::is_whitespace);
+                    } else {
+                        // comments are kept as written where possible.
+                        let exact = source
+                            .get_or_insert_with(|| Source::load(span))
+                            .as_ref()
+                            .filter(|_| span_pos.is_known())
+                            .and_then(|s| s.text(span_pos.start, span_pos.end))
+                            .and_then(|t| matching(t, &c));
+                        literal.push_str(&exact.unwrap_or(c));
+                    }
+                }
                 ItemElement::StartOpenTag(n) => {
-                    current_tag = n.value;
-                    if WHITESPACE_INSENSITIVE.contains(&current_tag.as_str()) {
-                        literal = literal.trim_end().to_owned();
+                    if options.lint && !tags.in_foreign() {
+                        lint_element(&n.value, n.span);
+                    }
+                    if preserved.is_none() && options.trims_around(&n.value) {
+                        if options.whitespace == Whitespace::Pretty {
+                            break_line(&mut literal, pinned, !items.is_empty(), tags.depth());
+                        } else {
+                            trim_literal(&mut literal, pinned);
+                        }
                     }
+                    omit_end_tag(&mut literal, last_end_tag, &tag_name(&n.value));
+                    tags.open(&n.value, n.span);
+                    attr_name = AttrName::default();
+                    current_tag = n.value;
                     literal.push_str(&format!("<{}", current_tag));
                     in_open_tag = true;
                 }
                 ItemElement::StartCloseTag(n) => {
+                    if options.lint && !tags.in_foreign() {
+                        lint_element(&n.value, n.span);
+                    }
+                    tags.close(&n.value, n.span);
                     current_tag = n.value;
-                    if WHITESPACE_INSENSITIVE.contains(&current_tag.as_str()) {
-                        literal = literal.trim_end().to_owned();
+                    if preserved.as_ref() == Some(&tag_name(&current_tag)) {
+                        if let Some(start) = raw_start.take() {
+                            let raw = literal.split_off(start);
+                            literal.push_str(&match preserved.as_deref() {
+                                Some("style") => minify::css(&raw),
+                                _ => minify::js(&raw),
+                            });
+                        }
+                        preserved = None;
+                    }
+                    if preserved.is_none() && options.trims_inside(&current_tag) {
+                        if options.whitespace == Whitespace::Pretty {
+                            break_line(&mut literal, pinned, !items.is_empty(), tags.depth());
+                        } else {
+                            trim_literal(&mut literal, pinned);
+                        }
                     }
+                    omit_end_tag(
+                        &mut literal,
+                        last_end_tag,
+                        &format!("/{}", tag_name(&current_tag)),
+                    );
                     literal.push_str(&format!("</{}", current_tag));
                 }
                 ItemElement::EndTag => {
-                    if WHITESPACE_INSENSITIVE.contains(&current_tag.as_str()) {
-                        skip_space = true;
+                    let self_closed = in_open_tag && literal.ends_with('/');
+                    if self_closed {
+                        tags.self_close(&current_tag);
+                    }
+                    // after a start tag we're inside the element, unless it has
+                    // no contents.
+                    let inside = in_open_tag && !self_closed && !is_void(&current_tag);
+                    if inside {
+                        if preserved.is_none()
+                            && WHITESPACE_PRESERVED.contains(&tag_name(&current_tag).as_str())
+                        {
+                            preserved = Some(tag_name(&current_tag));
+                        }
+                        skip_space = options.trims_inside(&current_tag);
+                    } else {
+                        skip_space = options.trims_around(&current_tag);
+                    }
+                    skip_space &= preserved.is_none();
+                    line_break = skip_space && options.whitespace == Whitespace::Pretty;
+                    literal.push('>');
+                    if options.minify {
+                        let tag = tag_name(&current_tag);
+                        if inside && (tag == "script" || tag == "style") {
+                            raw_start = Some(literal.len());
+                        } else if !in_open_tag && literal.ends_with(&format!("</{}>", current_tag))
+                        {
+                            let end = literal.len();
+                            end_tag = Some((tag, end - current_tag.len() - 3, end));
+                        }
                     }
-                    literal.push_str(">");
                     in_open_tag = false;
                 }
             }
@@ -322,19 +1123,98 @@ impl Parse for Template {
         if !literal.is_empty() {
             let mut span_pos = literal_start_pos;
             span_pos.move_end(prev_span_pos);
-            items.push(Item::new(span_pos, ItemElement::Literal(literal)));
+            items.push(Item::new(
+                literal_start,
+                span_pos,
+                ItemElement::Literal(literal),
+            ));
         }
-        Ok(Self { items })
+        tags.finish();
+        Ok(Self {
+            items,
+            block_size_hint: options.block_size_hint,
+            manifest: options.manifest,
+        })
     }
 }
 
 impl ToTokens for Template {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let items = self.items.iter();
+        // the defaults come from the manifest, so changing it rebuilds the
+        // template.
+        let manifest = self
+            .manifest
+            .as_ref()
+            .map(|path| quote! { const _: &[u8] = include_bytes!(#path); });
         let q = quote! {
+            #manifest
             #(#items)*
             Ok(())
         };
         q.to_tokens(tokens);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // Renders the static parts of a template, with `{}` standing in for
+    // blocks, so the whitespace handling can be checked without compiling.
+    fn literal(t: &Template) -> String {
+        t.items
+            .iter()
+            .map(|i| match &i.element {
+                ItemElement::Literal(l) => l.as_str(),
+                _ => "{}",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn spacing_from_positions() {
+        let t: Template = syn::parse_str(
+            "#![whitespace = \"collapse\"] <a>Hello,  world:{name}!</a> <div> x </div>",
+        )
+        .unwrap();
+        assert_eq!(literal(&t), "<a>Hello, world:{}!</a><div>x</div>");
+    }
+
+    #[test]
+    fn spacing_without_positions() {
+        let t: Template = syn::parse2(quote! {
+            #![whitespace = "collapse"]
+            <!doctype html>
+            <a href="/" class="x">Hello, world:{name}!</a> <div> x </div>
+        })
+        .unwrap();
+        assert_eq!(
+            literal(&t),
+            r#"<!doctype html><a href="/" class="x">Hello, world: {}!</a><div>x</div>"#,
+        );
+    }
+
+    // Run using `cargo test -p qtpl-macros --features minify`.
+    #[test]
+    #[cfg(feature = "minify")]
+    fn minify_feature() {
+        let list = "<ul><li>a</li> <li>b</li></ul>";
+        let t: Template = syn::parse_str(list).unwrap();
+        if cfg!(feature = "pretty") {
+            assert_eq!(
+                literal(&t),
+                "<ul>\n  <li>\n    a\n  </li>\n  <li>\n    b\n  </li>\n</ul>"
+            );
+        } else {
+            assert_eq!(literal(&t), "<ul><li>a<li>b</ul>");
+        }
+        let t: Template =
+            syn::parse_str(&format!("#![whitespace = \"preserve\"] {}", list)).unwrap();
+        assert_eq!(literal(&t), list);
+        let t: Template =
+            syn::parse_str(&format!("#![whitespace = \"collapse\"] {}", list)).unwrap();
+        assert_eq!(literal(&t), "<ul><li>a</li><li>b</li></ul>");
+    }
+}
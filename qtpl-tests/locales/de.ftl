@@ -0,0 +1,8 @@
+-brand = Acme
+
+hello = Hallo, { $name }!
+welcome = Willkommen bei { -brand }.
+cart = { $count ->
+    [one] Ein Artikel
+   *[other] { $count } Artikel
+} im Warenkorb von { $name }
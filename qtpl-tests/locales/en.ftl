@@ -0,0 +1,9 @@
+-brand = Acme
+
+hello = Hello, { $name }!
+welcome = Welcome to { -brand }.
+search = Search { $site }
+cart = { $count ->
+    [one] One item
+   *[other] { $count } items
+} in { $name }'s cart
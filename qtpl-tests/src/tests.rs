@@ -44,6 +44,22 @@ fn no_spaces_around_block() {
     assert_eq!(render_string!(hello("world")), "<a>Hello:world!</a>");
 }
 
+#[test]
+fn block_with_smart_pointer() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[tplfn]
+    fn hello(name: Rc<String>, title: Arc<String>) {
+        tpl! { <a title={!a title}>Hello, {name}!</a> }
+    }
+
+    assert_eq!(
+        render_string!(hello(Rc::new("world".into()), Arc::new("<hi>".into()))),
+        r#"<a title="&lt;hi&gt;">Hello, world!</a>"#
+    );
+}
+
 #[test]
 fn format_bytes() {
     #[tplfn]
@@ -74,6 +90,272 @@ fn whitespace_insensitive() {
     assert_eq!(render_string!(hello()), "<a>Hello,<div>world</div>!</a>");
 }
 
+#[test]
+fn whitespace_trim() {
+    #[tplfn]
+    fn hello(name: &str) {
+        tpl! { <a>Hello, <b>{name}</b> {-} ! <i> {-} x </i></a> }
+    }
+
+    assert_eq!(
+        render_string!(hello("world")),
+        "<a>Hello, <b>world</b>! <i>x </i></a>"
+    );
+}
+
+#[test]
+fn whitespace_forced() {
+    #[tplfn]
+    fn hello() {
+        tpl! {
+            <div>
+                <p>a</p>{~}<p>b</p>
+                {~n}
+                <p>c</p>{~}{-}<p>d</p>
+            </div>
+        }
+    }
+
+    assert_eq!(
+        render_string!(hello()),
+        "<div><p>a</p> <p>b</p>\n<p>c</p> <p>d</p></div>"
+    );
+}
+
+#[test]
+fn whitespace_preserve() {
+    #[tplfn(whitespace = "preserve")]
+    fn hello() {
+        tpl! {
+            <div>
+              <a>x</a>   y
+            </div>
+        }
+    }
+
+    assert_eq!(
+        render_string!(hello()),
+        "<div>\n              <a>x</a>   y\n            </div>"
+    );
+}
+
+#[test]
+fn whitespace_minify() {
+    #[tplfn]
+    fn hello() {
+        tpl! {
+            #![whitespace = "minify"]
+            <p> <a>x</a> <i> y  z </i> </p>
+        }
+    }
+
+    assert_eq!(render_string!(hello()), "<p><a>x</a><i>y z</i></p>");
+}
+
+#[test]
+fn whitespace_pretty() {
+    #[tplfn(whitespace = "pretty")]
+    fn hello(name: &str) {
+        tpl! {
+            <div><p>Hello, <b>{name}</b>!</p><ul><li>a</li><li><br></li></ul></div>
+            <pre><div> x </div></pre>
+        }
+    }
+
+    assert_eq!(
+        render_string!(hello("world")),
+        concat!(
+            "<div>\n",
+            "  <p>\n",
+            "    Hello, <b>world</b>!\n",
+            "  </p>\n",
+            "  <ul>\n",
+            "    <li>\n",
+            "      a\n",
+            "    </li>\n",
+            "    <li>\n",
+            "      <br>\n",
+            "    </li>\n",
+            "  </ul>\n",
+            "</div>\n",
+            "<pre><div> x </div></pre>",
+        )
+    );
+}
+
+#[test]
+fn whitespace_insensitive_custom() {
+    #[tplfn(whitespace_insensitive = "my-card")]
+    fn hello() {
+        tpl! { <a> <my-card> x </my-card> </a> }
+    }
+
+    assert_eq!(render_string!(hello()), "<a><my-card>x</my-card></a>");
+}
+
+#[test]
+fn whitespace_pre() {
+    #[tplfn]
+    fn hello() {
+        tpl! {
+            <div>
+                <pre> a  b
+                    c </pre>
+                <textarea>  x  </textarea>
+            </div>
+        }
+    }
+
+    assert_eq!(
+        render_string!(hello()),
+        "<div><pre> a  b\n                    c </pre><textarea>  x  </textarea></div>"
+    );
+}
+
+#[test]
+fn whitespace_verbatim() {
+    #[tplfn]
+    fn hello() {
+        tpl! {
+            <div>
+                <pre>f(a,  b)	[ x ]</pre>
+                <script>
+                    let  x = [1,  2];
+                </script>
+            </div>
+        }
+    }
+
+    assert_eq!(
+        render_string!(hello()),
+        concat!(
+            "<div><pre>f(a,  b)\t[ x ]</pre><script>\n",
+            "                    let  x = [1,  2];\n",
+            "                </script></div>",
+        )
+    );
+}
+
+#[test]
+fn minify() {
+    #[tplfn(minify)]
+    fn hello(name: &str) {
+        tpl! {
+            <!-- navigation -->
+            <ul class="nav" style="color : red ;">
+                <li><a href="/a">A</a></li>
+                <li title="a b">Hello, {name}</li>
+            </ul>
+            <p>one</p>
+            <p><svg><path d="M0"/></svg></p>
+            <table><thead><tr><th>a</th></tr></thead></table>
+            <dl><dt>t</dt></dl>
+            <script>
+                let x = [1,  2];
+
+                f(x, "a \
+                    b");
+            </script>
+        }
+    }
+
+    assert_eq!(
+        render_string!(hello("world")),
+        concat!(
+            r#"<ul class=nav style=color:red><li><a href=/a>A</a>"#,
+            r#"<li title="a b">Hello, world</ul>"#,
+            r#"<p>one<p><svg><path d=M0 /></svg>"#,
+            "<table><thead><tr><th>a</thead></table>",
+            "<dl><dt>t</dt></dl>",
+            "<script>                let x = [1,  2];\n",
+            "                f(x, \"a \\\n",
+            "                    b\");</script>",
+        )
+    );
+}
+
+#[test]
+fn comment() {
+    #[tplfn]
+    fn hello() {
+        tpl! {
+            <!--
+              <b>  x, y
+            -->
+            <p>a <!--z--> b</p>
+        }
+    }
+
+    assert_eq!(
+        render_string!(hello()),
+        "<!--\n              <b>  x, y\n            --><p>a <!--z--> b</p>"
+    );
+}
+
+#[test]
+fn template_comment() {
+    #[tplfn]
+    fn hello(name: &str) {
+        tpl! {
+            {# greets the user, the <p> is always shown #}
+            <p>Hello, {# the name #} {name}{# ! #}</p>
+        }
+    }
+
+    assert_eq!(render_string!(hello("world")), "<p>Hello, world</p>");
+}
+
+#[test]
+fn custom_directive() {
+    use std::io::{Result, Write};
+
+    fn money(w: &mut dyn Write, cents: &u64) -> Result<()> {
+        write!(w, "${}.{:02}", cents / 100, cents % 100)
+    }
+
+    mod fmt {
+        pub fn upper(w: &mut dyn std::io::Write, s: &&str) -> std::io::Result<()> {
+            write!(w, "{}", qtpl::escape(&s.to_uppercase()))
+        }
+    }
+
+    #[tplfn(directive(cash = "money"))]
+    fn price(name: &str, cents: u64) {
+        tpl! { <p>{name | fmt::upper}: {!cash cents}, or {cents | cash}</p> }
+    }
+
+    assert_eq!(
+        render_string!(price("<tea>", 1205)),
+        "<p>&lt;TEA&gt;: $12.05, or $12.05</p>"
+    );
+}
+
+#[test]
+fn filters() {
+    fn quoted(w: &mut dyn std::io::Write, s: &str, q: &str) -> std::io::Result<()> {
+        write!(w, "{}{}{}", q, qtpl::escape(s), q)
+    }
+
+    #[tplfn]
+    fn t(q: &str, items: Vec<String>, flags: u8) {
+        tpl! {
+            <a href={q | urlencode}>{q | lower | truncate(5)}</a>
+            {items | join("+") | upper | quoted("'")}
+            {&(flags | 2).to_string()}
+            {items.len() | pluralize("y", "ies")}
+        }
+    }
+
+    assert_eq!(
+        render_string!(t("A&B c/d", vec!["x".into(), "<y>".into()], 1)),
+        r#"<a href="A%26B%20c%2Fd">a&amp;b…</a> 'X+&lt;Y&gt;' 3 ies"#
+    );
+    assert_eq!(
+        render_string!(t("ab", vec!["x".into()], 0)),
+        r#"<a href="ab">ab</a> 'X' 2 y"#
+    );
+}
+
 const XSS: &str = r#"You're <script>alert("pawned")</script>!"#;
 
 #[test]
@@ -88,11 +370,26 @@ fn escape_content() {
     );
 }
 
+#[test]
+fn escape_to() {
+    // escapes at every offset within and across the 16 byte chunks.
+    for len in 0..40 {
+        for at in 0..len {
+            let mut s: String = "abcdefghijklmnopqrstuvwxyz0123456789ABCD"[..len].into();
+            s.replace_range(at..at + 1, "<");
+            s.push_str(r#"&"'/>"#);
+            let mut w = vec![];
+            qtpl::escape_to(&mut w, s.as_bytes()).unwrap();
+            assert_eq!(String::from_utf8(w).unwrap(), qtpl::escape(&s).to_string());
+        }
+    }
+}
+
 #[test]
 fn escape_attr() {
     #[tplfn]
     fn t(v: &str) {
-        tpl! { <a id={v}> }
+        tpl! { #![fragment] <a id={v}> }
     }
     assert_eq!(render_string!(t("me")), r#"<a id="me">"#);
     assert_eq!(
@@ -101,6 +398,152 @@ fn escape_attr() {
     );
 }
 
+#[test]
+fn boolean_attr() {
+    #[tplfn]
+    fn t(disabled: bool, checked: bool) {
+        tpl! { <input type="checkbox" disabled={disabled} checked={checked}> }
+    }
+    assert_eq!(
+        render_string!(t(true, false)),
+        r#"<input type="checkbox" disabled>"#
+    );
+    assert_eq!(
+        render_string!(t(false, true)),
+        r#"<input type="checkbox" checked>"#
+    );
+}
+
+#[test]
+fn option_attr() {
+    #[tplfn]
+    fn t(title: Option<String>) {
+        tpl! { <a href="/" title={title}>x</a> }
+    }
+    assert_eq!(
+        render_string!(t(Some("<me>".into()))),
+        r#"<a href="/" title="&lt;me&gt;">x</a>"#
+    );
+    assert_eq!(render_string!(t(None)), r#"<a href="/">x</a>"#);
+}
+
+#[test]
+fn string_like_attr() {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    struct Url(String);
+
+    impl AsRef<str> for Url {
+        fn as_ref(&self) -> &str {
+            &self.0
+        }
+    }
+
+    #[tplfn]
+    fn t(a: Box<str>, b: Rc<str>, c: Option<Arc<str>>, d: Url) {
+        tpl! { <a id={a} title={b} rel={c} href={d}>x</a> }
+    }
+    assert_eq!(
+        render_string!(t("a".into(), "b".into(), None, Url("/<d>".into()))),
+        r#"<a id="a" title="b" href="&#x2f;&lt;d&gt;">x</a>"#
+    );
+}
+
+#[test]
+fn class_list_attr() {
+    #[tplfn]
+    fn t(kind: &str, active: bool) {
+        tpl! { <a class={["btn", kind, ("active", active)]}>x</a> }
+    }
+    assert_eq!(
+        render_string!(t("big", true)),
+        r#"<a class="btn big active">x</a>"#
+    );
+    assert_eq!(
+        render_string!(t("big", false)),
+        r#"<a class="btn big">x</a>"#
+    );
+}
+
+#[test]
+fn spread_attrs() {
+    use std::collections::BTreeMap;
+
+    #[tplfn]
+    fn t(attrs: &BTreeMap<String, Option<String>>) {
+        tpl! { <a href="/" {..attrs}>x</a> }
+    }
+    let mut attrs = BTreeMap::new();
+    attrs.insert("id".to_string(), Some("<me>".to_string()));
+    attrs.insert("title".to_string(), None);
+    assert_eq!(
+        render_string!(t(&attrs)),
+        r#"<a href="/" id="&lt;me&gt;">x</a>"#
+    );
+
+    attrs.insert("a b".to_string(), None);
+    let err = t(&mut vec![], &attrs).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn void_and_self_closing() {
+    #[tplfn]
+    fn t() {
+        tpl! { <p>a<br>b<img src="x" /><svg><path d="M0"/></svg></p> }
+    }
+    assert_eq!(
+        render_string!(t()),
+        r#"<p>a<br>b<img src="x" /><svg><path d="M0"/></svg></p>"#,
+    );
+}
+
+#[test]
+fn optional_end_tags() {
+    #[tplfn]
+    fn t() {
+        tpl! { <ul><li>a<li>b</ul><p>c<p>d<div>e</div> }
+    }
+    assert_eq!(
+        render_string!(t()),
+        "<ul><li>a<li>b</ul><p>c<p>d<div>e</div>"
+    );
+}
+
+#[test]
+fn fragment() {
+    #[tplfn]
+    fn open() {
+        tpl! { #![fragment] <main><div> }
+    }
+    #[tplfn]
+    fn close() {
+        tpl! { #![fragment] <li>x</div></main> }
+    }
+    assert_eq!(render_string!(open()), "<main><div>");
+    assert_eq!(render_string!(close()), "<li>x</div></main>");
+}
+
+#[test]
+fn lint() {
+    #[tplfn]
+    fn t(label: &str) {
+        tpl! {
+            #![lint]
+            <!doctype html>
+            <my-card data-id="1" aria-label={label} class="card">
+                <svg viewBox="0 0 1 1"><path d="M0"/></svg>
+            </my-card>
+            <th abbr="n" onpointerdown="f()" ontouchstart="f()" onfocusin="f()">x</th>
+        }
+    }
+    assert_eq!(
+        render_string!(t("x")),
+        r#"<!doctype html><my-card data-id="1" aria-label="x" class="card"> <svg viewBox="0 0 1 1"><path d="M0"/></svg> </my-card><th abbr="n" onpointerdown="f()" ontouchstart="f()" onfocusin="f()">x</th>"#,
+    );
+}
+
 #[test]
 fn readme_example() {
     use qtpl::{render, render_string, tpl, tplfn};
@@ -149,3 +592,591 @@ fn readme_example() {
         )
     );
 }
+
+#[test]
+fn size_hint() -> std::io::Result<()> {
+    #[tplfn(size_hint)]
+    fn hello(name: &str) {
+        tpl! { <a>Hello, {name}!</a> }
+    }
+
+    #[tplfn(size_hint, block_size_hint = 4)]
+    fn greet(name: Option<&str>) {
+        if let Some(name) = name {
+            tpl! { <a>Hello, {name}!</a> }
+        } else {
+            tpl! { <a>Hello, stranger!</a> }
+        }
+    }
+
+    assert_eq!(HELLO_SIZE_HINT, 31);
+    assert_eq!(GREET_SIZE_HINT, 23);
+    let out = qtpl::render!(hello("world"), size_hint = HELLO_SIZE_HINT);
+    assert!(out.capacity() >= HELLO_SIZE_HINT);
+    let out = qtpl::render!(greet(None), size_hint = GREET_SIZE_HINT);
+    assert!(out.capacity() >= GREET_SIZE_HINT);
+    assert_eq!(qtpl::render!(greet(None)).len(), 23);
+    Ok(())
+}
+
+#[test]
+fn automatic_size_hint() -> std::io::Result<()> {
+    use std::io::{Result, Write};
+
+    mod views {
+        use qtpl::{tpl, tplfn};
+
+        #[tplfn(block_size_hint = 1000)]
+        pub fn big(name: &str) {
+            tpl! { <p>{name}</p> }
+        }
+    }
+
+    trait View {
+        fn make(w: &mut dyn Write) -> Result<()>;
+    }
+
+    struct A;
+
+    impl A {
+        #[tplfn(block_size_hint = 1000)]
+        fn show(&self) {
+            tpl! { <p>{"a"}</p> }
+        }
+    }
+
+    impl View for A {
+        #[tplfn(block_size_hint = 1000)]
+        fn make() {
+            tpl! { <p>{"a"}</p> }
+        }
+    }
+
+    // the hint is that of the function rendered, not those it calls.
+    #[tplfn]
+    fn small() {
+        tpl! { <div>{!t views::big("x")}</div> }
+    }
+
+    use views::big;
+    assert!(qtpl::render!(big("x")).capacity() >= 1000);
+    assert!(qtpl::render!(A.show()).capacity() >= 1000);
+    assert!(qtpl::render!(A::make()).capacity() >= 1000);
+    assert!(qtpl::render!(small()).capacity() < 1000);
+    assert_eq!(render_string!(small()), "<div><p>x</p></div>");
+    Ok(())
+}
+
+#[test]
+fn render_without_size_hint() {
+    use std::io::{Result, Write};
+
+    mod views {
+        use qtpl::{tpl, tplfn};
+
+        #[tplfn]
+        pub fn home(name: &str) {
+            tpl! { <p>{name}</p> }
+        }
+    }
+
+    trait View {
+        fn make(w: &mut dyn Write) -> Result<()>;
+    }
+
+    struct A;
+
+    impl View for A {
+        #[tplfn]
+        fn make() {
+            tpl! { <p>a</p> }
+        }
+    }
+
+    fn plain(w: &mut dyn Write, name: &str) -> Result<()> {
+        write!(w, "<p>{}</p>", name)
+    }
+
+    use views::home;
+    assert_eq!(render_string!(home("x")), "<p>x</p>");
+    assert_eq!(render_string!(plain("y")), "<p>y</p>");
+    assert_eq!(render_string!(A::make()), "<p>a</p>");
+}
+
+#[test]
+#[forbid(unsafe_code)]
+fn try_render_string() {
+    use qtpl::tpl as html;
+    use qtpl::try_render_string;
+
+    #[tplfn]
+    fn text(name: &str) {
+        tpl! { <a title={!a name} class={["x"]}>Hello, {name}!</a> }
+    }
+
+    #[tplfn]
+    fn bytes(b: &[u8]) {
+        tpl! { <a>{!b b}</a> }
+    }
+
+    #[tplfn]
+    fn direct(b: &[u8]) {
+        w.write_all(b)
+    }
+
+    #[tplfn]
+    fn renamed(b: &[u8]) {
+        html! { <a>{!b b}</a> }
+    }
+
+    #[tplfn]
+    fn answer(a: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let a: i8 = a.parse()?;
+        tpl! {{&a.to_string()}}
+    }
+
+    assert_eq!(
+        try_render_string!(text("<b>")).unwrap(),
+        r#"<a title="&lt;b&gt;" class="x">Hello, &lt;b&gt;!</a>"#
+    );
+    assert_eq!(try_render_string!(bytes(b"ok")).unwrap(), "<a>ok</a>");
+    let err = try_render_string!(bytes(b"\xff")).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(try_render_string!(direct(b"\xff")).is_err());
+    assert!(try_render_string!(renamed(b"\xff\xfe")).is_err());
+    assert_eq!(try_render_string!(answer("42")).unwrap(), "42");
+    let err = try_render_string!(answer("x")).unwrap_err();
+    assert_eq!(err.to_string(), "invalid digit found in string");
+}
+
+#[test]
+fn render_callables() -> std::io::Result<()> {
+    use qtpl::render;
+    use std::io::Write;
+
+    struct Page {
+        title: String,
+    }
+
+    impl Page {
+        #[tplfn]
+        fn header(&self) {
+            tpl! { <h1>{self.title}</h1> }
+        }
+
+        #[tplfn]
+        fn body(&self, text: &str) {
+            tpl! { <p>{text}</p> }
+        }
+
+        #[tplfn]
+        fn footer(year: &str) {
+            tpl! { <footer>{year}</footer> }
+        }
+    }
+
+    #[tplfn]
+    fn count<T>(items: &[T]) {
+        tpl! { <b>{&items.len().to_string()}</b> }
+    }
+
+    let page = Page {
+        title: "Home".into(),
+    };
+    let hello = |w: &mut dyn Write| -> std::io::Result<()> {
+        tpl! { <i>Hello</i> }
+    };
+
+    assert_eq!(render!(page.header()), b"<h1>Home</h1>");
+    assert_eq!(render_string!(page.body("Hi")), "<p>Hi</p>");
+    assert_eq!(
+        render_string!(Page::footer("2020")),
+        "<footer>2020</footer>"
+    );
+    assert_eq!(render_string!(count::<u8>(&[1, 2])), "<b>2</b>");
+    assert_eq!(render_string!(hello), "<i>Hello</i>");
+    assert_eq!(
+        render_string!(|w: &mut dyn Write| page.header(w)),
+        "<h1>Home</h1>"
+    );
+    Ok(())
+}
+
+#[test]
+fn tpl_fn_callables() {
+    use std::io::{Result, Write};
+
+    type Fragment = Box<dyn Fn(&mut dyn Write) -> Result<()>>;
+
+    struct Item {
+        name: String,
+    }
+
+    impl Item {
+        #[tplfn]
+        fn render(&self) {
+            tpl! { <li>{self.name}</li> }
+        }
+    }
+
+    struct Layout {
+        items: Vec<Item>,
+        footer: Fragment,
+    }
+
+    impl Layout {
+        #[tplfn]
+        fn sidebar(&self) {
+            for item in &self.items {
+                item.render(w)?;
+            }
+            Ok(())
+        }
+
+        #[tplfn]
+        fn page(&self) {
+            tpl! { <ul>{!t self.sidebar()}{!t self.items[0].render()}</ul>{!t self.footer} }
+        }
+    }
+
+    let layout = Layout {
+        items: vec![Item { name: "a".into() }, Item { name: "b".into() }],
+        footer: Box::new(|w| w.write_all(b"<footer>x</footer>")),
+    };
+    assert_eq!(
+        render_string!(layout.page()),
+        "<ul><li>a</li><li>b</li><li>a</li></ul><footer>x</footer>"
+    );
+}
+
+#[test]
+fn dyn_template() {
+    use qtpl::DynTemplate;
+
+    #[tplfn]
+    fn widget(name: &str) {
+        tpl! { <li>{name}</li> }
+    }
+
+    #[tplfn]
+    fn checked(n: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let n: u8 = n.parse()?;
+        tpl! { <b>{&n.to_string()}</b> }
+    }
+
+    #[tplfn]
+    fn list(widgets: &[DynTemplate]) {
+        tpl! { <ul>{!t widgets[0]}{!t widgets[1].render()}</ul> }
+    }
+
+    let name = String::from("b");
+    let widgets = vec![
+        DynTemplate::new(|w| widget(w, "a")),
+        DynTemplate::new(move |w| widget(w, &name)),
+    ];
+    assert_eq!(
+        render_string!(list(&widgets)),
+        "<ul><li>a</li><li>b</li></ul>"
+    );
+    assert_eq!(format!("{:?}", widgets[0]), "DynTemplate");
+
+    let ok: DynTemplate<_> = DynTemplate::new(|w| checked(w, "1"));
+    let err: DynTemplate<_> = DynTemplate::new(|w| checked(w, "x"));
+    let mut w = vec![];
+    ok.render(&mut w).unwrap();
+    assert_eq!(w, b"<b>1</b>");
+    assert!(err.render(&mut w).is_err());
+}
+
+#[test]
+fn tpl_closure() {
+    use qtpl::{tpl_closure, DynTemplate};
+    use std::io::{Result, Write};
+
+    #[tplfn]
+    fn layout(title: &str, body: &dyn Fn(&mut dyn Write) -> Result<()>) {
+        tpl! { <main><h1>{title}</h1>{!t body}</main> }
+    }
+
+    fn greeting(name: String) -> DynTemplate<'static> {
+        DynTemplate::new(tpl_closure!(move |w| { <p>Hi {name}</p> }))
+    }
+
+    let items = ["a", "b"];
+    let body = tpl_closure!(|w| {
+        <ul><li>{items[0]}</li><li>{items[1]}</li></ul>
+    });
+    assert_eq!(
+        render_string!(layout("List", &body)),
+        "<main><h1>List</h1><ul><li>a</li><li>b</li></ul></main>"
+    );
+    assert_eq!(
+        render_string!(layout("Hi", &*greeting("<b>".into()))),
+        "<main><h1>Hi</h1><p>Hi &lt;b&gt;</p></main>"
+    );
+}
+
+#[test]
+fn i18n_fluent() {
+    use qtpl::i18n::Messages;
+
+    // the catalog is given in [package.metadata.qtpl] of Cargo.toml.
+    #[tplfn]
+    fn page(i18n: &Messages, name: &str) {
+        tpl! {
+            <h1>{!i18n "welcome"}</h1>
+            <p>{!i18n "hello", name = name}</p>
+            <input placeholder={!i18n "search", site = "<Acme>"}>
+        }
+    }
+
+    let en = Messages::from_ftl(include_str!("../locales/en.ftl")).unwrap();
+    let de = Messages::from_ftl(include_str!("../locales/de.ftl")).unwrap();
+    assert_eq!(
+        render_string!(page(&en, "<b>")),
+        "<h1>Welcome to Acme.</h1><p>Hello, &lt;b&gt;!</p>\
+         <input placeholder=\"Search &lt;Acme&gt;\">"
+    );
+    // untranslated messages are written as their key.
+    assert_eq!(
+        render_string!(page(&de, "Bob")),
+        "<h1>Willkommen bei Acme.</h1><p>Hallo, Bob!</p><input placeholder=\"search\">"
+    );
+}
+
+#[test]
+fn i18n_plural() {
+    use qtpl::i18n::{Message, Messages};
+    use qtpl::locale::{Locale, Plural};
+
+    #[tplfn]
+    fn cart(i18n: &Messages, locale: Locale, count: usize) {
+        tpl! {
+            <p title={!plural "cart", count = count, name = "Bob"}>
+                {!plural "cart", count = count, name = "<Bob>"}
+            </p>
+        }
+    }
+
+    let en = Messages::from_ftl(include_str!("../locales/en.ftl")).unwrap();
+    let de = Messages::from_ftl(include_str!("../locales/de.ftl")).unwrap();
+    let en_us = Locale::new("en").unwrap();
+    assert_eq!(
+        render_string!(cart(&en, en_us, 1)),
+        "<p title=\"One item in Bob&#x27;s cart\">One item in &lt;Bob&gt;&#x27;s cart</p>"
+    );
+    // the count is formatted for the locale.
+    assert_eq!(
+        render_string!(cart(&de, Locale::new("de").unwrap(), 1200)),
+        "<p title=\"1.200 Artikel im Warenkorb von Bob\">\
+         1.200 Artikel im Warenkorb von &lt;Bob&gt;</p>"
+    );
+
+    let mut pl = Messages::new();
+    let forms = [
+        (Plural::One, "{count} produkt"),
+        (Plural::Few, "{count} produkty"),
+        (Plural::Other, "{count} produktów"),
+    ];
+    pl.insert("cart", Message::plural("count", &forms).unwrap());
+    let pl_pl = Locale::new("pl").unwrap();
+    assert_eq!(
+        render_string!(cart(&pl, pl_pl, 3)),
+        "<p title=\"3 produkty\">3 produkty</p>"
+    );
+    assert_eq!(
+        render_string!(cart(&pl, pl_pl, 5)),
+        "<p title=\"5 produktów\">5 produktów</p>"
+    );
+}
+
+#[test]
+fn i18n_gettext() {
+    use qtpl::i18n::{Catalog, Messages};
+    use std::collections::HashMap;
+    use std::fmt::Display;
+    use std::io::{Result, Write};
+
+    #[tplfn]
+    fn nav(i18n: &dyn Catalog, count: usize) {
+        tpl! {
+            #![i18n_catalog = "locales/en.po"]
+            <p>{!i18n "Hello, {name}!", name = count} {!i18n "Sign out"}</p>
+        }
+    }
+
+    // writes the keys and arguments instead of the messages.
+    struct Keys;
+
+    impl Catalog for Keys {
+        fn write_message(
+            &self,
+            w: &mut dyn Write,
+            key: &str,
+            args: &[(&str, &dyn Display)],
+        ) -> Result<()> {
+            write!(w, "[{}", key)?;
+            for (name, value) in args {
+                write!(w, " {}={}", name, value)?;
+            }
+            write!(w, "]")
+        }
+    }
+
+    let mut locales = HashMap::new();
+    locales.insert(
+        "en",
+        Messages::from_po(include_str!("../locales/en.po")).unwrap(),
+    );
+    locales.insert(
+        "de",
+        Messages::from_po(include_str!("../locales/de.po")).unwrap(),
+    );
+    assert_eq!(
+        render_string!(nav(&locales["en"], 3)),
+        "<p>Hello, 3! Sign out</p>"
+    );
+    assert_eq!(
+        render_string!(nav(&locales["de"], 3)),
+        "<p>Hallo, 3! Abmelden</p>"
+    );
+    assert_eq!(
+        render_string!(nav(&Keys, 3)),
+        "<p>[Hello, {name}! name=3] [Sign out]</p>"
+    );
+}
+
+#[test]
+fn locale_plural() {
+    use qtpl::locale::{Locale, Plural};
+
+    let plural = |tag: &str, n: &str| Locale::new(tag).unwrap().plural(n);
+    assert_eq!(plural("en", "1"), Plural::One);
+    assert_eq!(plural("en", "1.0"), Plural::Other);
+    assert_eq!(plural("en", "0"), Plural::Other);
+    assert_eq!(plural("fr", "0"), Plural::One);
+    assert_eq!(plural("fr", "1.5"), Plural::One);
+    assert_eq!(plural("fr", "1000000"), Plural::Many);
+    assert_eq!(plural("es", "1.0"), Plural::One);
+    assert_eq!(plural("ja", "1"), Plural::Other);
+    assert_eq!(plural("pl", "22"), Plural::Few);
+    assert_eq!(plural("pl", "12"), Plural::Many);
+    assert_eq!(plural("pl", "1.5"), Plural::Other);
+    assert_eq!(plural("ru", "21"), Plural::One);
+    assert_eq!(plural("ru", "11"), Plural::Many);
+    assert_eq!(plural("uk", "3"), Plural::Few);
+    assert_eq!(plural("pt-PT", "0"), Plural::Other);
+    assert_eq!(plural("pt-BR", "0"), Plural::One);
+}
+
+#[test]
+fn locale_format() {
+    use qtpl::locale::{Date, DateStyle, Locale};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let en = Locale::new("en_US").unwrap();
+    let de = Locale::new("de-AT").unwrap();
+    let es = Locale::new("es").unwrap();
+    let sv = Locale::new("sv").unwrap();
+    assert_eq!((en.tag(), de.tag()), ("en", "de"));
+    assert_eq!(Locale::new("pt-pt").unwrap().tag(), "pt-PT");
+    assert!(Locale::new("xx").is_none());
+    assert_eq!(Locale::default(), en);
+
+    assert_eq!(en.format_number(1234567.891), "1,234,567.891");
+    assert_eq!(en.format_number(0.1 + 0.2), "0.3");
+    assert_eq!(en.format_number(2.0005), "2");
+    assert_eq!(en.format_number(2.0015), "2.002");
+    assert_eq!(en.format_number(-999.9999), "-1,000");
+    assert_eq!(en.format_number(f64::NAN), "NaN");
+    assert_eq!(de.format_number(-1234.5), "-1.234,5");
+    assert_eq!(es.format_number(1234), "1234");
+    assert_eq!(es.format_number(12345), "12.345");
+    assert_eq!(sv.format_number(-12345), "\u{2212}12\u{a0}345");
+
+    assert_eq!(en.format_currency(1234.5, "USD"), "$1,234.50");
+    assert_eq!(en.format_currency(-3, "EUR"), "-€3.00");
+    assert_eq!(en.format_currency(5, "CHF"), "CHF\u{a0}5.00");
+    assert_eq!(en.format_currency(1234.5, "JPY"), "JPY\u{a0}1,234");
+    assert_eq!(de.format_currency(1234.5, "EUR"), "1.234,50\u{a0}€");
+    assert_eq!(
+        Locale::new("nl").unwrap().format_currency(-5, "EUR"),
+        "€\u{a0}-5,00"
+    );
+    assert_eq!(
+        Locale::new("ja").unwrap().format_currency(1500, "JPY"),
+        "￥1,500"
+    );
+
+    let d = Date::new(2024, 2, 29).unwrap();
+    assert!(Date::new(2023, 2, 29).is_none());
+    assert_eq!(en.format_date(d, DateStyle::Short), "2/29/24");
+    assert_eq!(en.format_date(d, DateStyle::Long), "February 29, 2024");
+    assert_eq!(de.format_date(d, DateStyle::Short), "29.02.24");
+    assert_eq!(es.format_date(d, DateStyle::Long), "29 de febrero de 2024");
+    let ru = Locale::new("ru").unwrap();
+    assert_eq!(ru.format_date(d, DateStyle::Long), "29 февраля 2024 г.");
+    let zh = Locale::new("zh-CN").unwrap();
+    assert_eq!(zh.format_date(d, DateStyle::Long), "2024年2月29日");
+
+    let t = UNIX_EPOCH + Duration::from_secs(1_709_164_800);
+    assert_eq!(Date::from(t), d);
+    assert_eq!(
+        Date::from(UNIX_EPOCH - Duration::from_secs(86_400)),
+        Date::new(1969, 12, 31).unwrap()
+    );
+}
+
+#[test]
+fn locale_directives() {
+    use qtpl::locale::{Date, Locale};
+
+    #[tplfn]
+    fn cart(locale: &Locale, items: usize, total: f64, date: Date) {
+        tpl! {
+            <p title={!number items}>
+                {!plural items, one = "# item", few = "# items (few)", other = "# items"},
+                {!currency total, "EUR"}, {!date date} or {!date date, long}
+            </p>
+        }
+    }
+
+    let date = Date::new(2024, 1, 2).unwrap();
+    assert_eq!(
+        render_string!(cart(&Locale::new("en").unwrap(), 1, 9.99, date)),
+        "<p title=\"1\">1 item, €9.99, 1&#x2f;2&#x2f;24 or January 2, 2024</p>"
+    );
+    assert_eq!(
+        render_string!(cart(&Locale::new("pl").unwrap(), 1234, 5.0, date)),
+        "<p title=\"1234\">1234 items (few), 5,00\u{a0}€, 2.01.2024 or 2 stycznia 2024</p>"
+    );
+}
+
+#[test]
+fn locale_argument() {
+    use qtpl::locale::{Date, Locale};
+
+    struct Context {
+        locale: Locale,
+    }
+
+    #[tplfn]
+    fn row(ctx: &Context, n: f64, date: Date) {
+        tpl! {
+            <td>{!number n, locale = ctx.locale}</td>
+            <td>{!currency n, "EUR", locale = ctx.locale}</td>
+            <td>{!date date, locale = ctx.locale} {!date date, long, locale = ctx.locale}</td>
+            <td>{!plural n, one = "# item", locale = ctx.locale, other = "# items"}</td>
+        }
+    }
+
+    let ctx = Context {
+        locale: Locale::new("de").unwrap(),
+    };
+    let date = Date::new(2024, 3, 1).unwrap();
+    assert_eq!(
+        render_string!(row(&ctx, 1234.5, date)),
+        "<td>1.234,5</td><td>1.234,50\u{a0}€</td>\
+         <td>01.03.24 1. März 2024</td><td>1.234,5 items</td>"
+    );
+}
@@ -0,0 +1,5 @@
+use qtpl::tpl_closure;
+
+fn main() {
+    let _ = tpl_closure!(|out| { <p>x</p> });
+}
@@ -0,0 +1,8 @@
+use qtpl::{tpl, tplfn};
+
+#[tplfn]
+fn unclosed() {
+    tpl! { <p>x</p> <!-- y }
+}
+
+fn main() {}
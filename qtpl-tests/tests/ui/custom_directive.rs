@@ -0,0 +1,12 @@
+use qtpl::{tpl, tplfn};
+
+fn money(w: &mut dyn std::io::Write, cents: &u64) -> std::io::Result<()> {
+    write!(w, "{}", cents)
+}
+
+#[tplfn(directive(money = "money", b = "money"))]
+fn typo(cents: u64) {
+    tpl! { <p>{!mony cents}</p> }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use qtpl::{tpl, tplfn};
+
+#[tplfn]
+fn whitespace() {
+    tpl! { <p>a{~x}b</p> }
+}
+
+#[tplfn]
+fn spread(attrs: &[(&str, &str)]) {
+    tpl! { <p>{..attrs.iter().copied()}</p> }
+}
+
+fn main() {}
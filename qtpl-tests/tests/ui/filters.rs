@@ -0,0 +1,17 @@
+use qtpl::{tpl, tplfn};
+
+fn money(w: &mut dyn std::io::Write, cents: &u64) -> std::io::Result<()> {
+    write!(w, "{}", cents)
+}
+
+#[tplfn]
+fn not_last(cents: u64) {
+    tpl! { <p>{cents | money | upper}</p> }
+}
+
+#[tplfn]
+fn bitwise_or(is_admin: bool, is_owner: bool) {
+    tpl! { <button disabled={is_admin | is_owner}></button> }
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+use qtpl::i18n::Messages;
+use qtpl::{tpl, tplfn};
+
+// catalogs are relative to the crate trybuild creates in target/tests/trybuild.
+
+#[tplfn(i18n_catalog = "../../../../qtpl-tests/locales/en.ftl")]
+fn typo(i18n: &Messages) {
+    tpl! { <p>{!i18n "helo", name = "Bob"}</p> }
+}
+
+#[tplfn(i18n_catalog = "../../../../qtpl-tests/locales/en.ftl")]
+fn arguments(i18n: &Messages) {
+    tpl! { <p>{!i18n "hello", nme = "Bob"}</p> }
+}
+
+#[tplfn(i18n_catalog = "../../../../qtpl-tests/locales/en.ftl")]
+fn plural(i18n: &Messages, locale: qtpl::locale::Locale) {
+    tpl! { <p>{!i18n "cart", count = 2, name = "Bob"} {!plural "hello", name = "Bob"}</p> }
+}
+
+#[tplfn]
+fn no_catalog(i18n: &Messages) {
+    tpl! { <p>{!i18n "hello", name = "Bob"}</p> }
+}
+
+#[tplfn(i18n_catalog = "locales/missing.ftl")]
+fn missing(i18n: &Messages) {
+    tpl! { <p>{!i18n "hello", name = "Bob"}</p> }
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+use qtpl::i18n::Messages;
+use qtpl::locale::Locale;
+use qtpl::{tpl, tplfn};
+
+// the directives use `locale` and `i18n` from the scope of the template.
+
+#[tplfn]
+fn number(n: u32) {
+    tpl! { <p>{!number n}</p> }
+}
+
+#[tplfn(i18n_catalog = "../../../../qtpl-tests/locales/en.ftl")]
+fn message(locale: &Locale) {
+    tpl! { <p>{!i18n "welcome"} {!number 1, locale = locale}</p> }
+}
+
+#[tplfn(i18n_catalog = "../../../../qtpl-tests/locales/en.ftl")]
+fn plural(i18n: &Messages) {
+    tpl! { <p>{!plural "cart", count = 2, name = "Bob"}</p> }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use qtpl::{tpl, tplfn};
+
+#[tplfn]
+fn typos() {
+    tpl! {
+        #![lint]
+        <dvi clas="x" data-ok="1" onclick="f()" on-click="f()">y</dvi>
+    }
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+use qtpl::locale::Locale;
+use qtpl::{tpl, tplfn};
+
+#[tplfn]
+fn plural(locale: &Locale, n: u32) {
+    tpl! { <p>{!plural n, one = "# item", several = "# items"}</p> }
+}
+
+#[tplfn]
+fn currency(locale: &Locale, n: f64) {
+    tpl! { <p>{!currency n, "euro"}</p> }
+}
+
+#[tplfn]
+fn date(locale: &Locale, d: qtpl::locale::Date) {
+    tpl! { <p>{!date d, full}</p> }
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+use qtpl::{tpl, tplfn};
+
+#[tplfn]
+fn unknown() {
+    tpl! {
+        #![fragmnet]
+        <p>x</p>
+    }
+}
+
+#[tplfn(whitespace = "tight")]
+fn whitespace() {
+    tpl! { <p>x</p> }
+}
+
+struct Page;
+
+impl Page {
+    #[tplfn(size_hint)]
+    fn header(&self) {
+        tpl! { <p>x</p> }
+    }
+}
+
+fn main() {}
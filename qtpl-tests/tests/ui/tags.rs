@@ -0,0 +1,42 @@
+use qtpl::{tpl, tplfn};
+
+#[tplfn]
+fn unclosed() {
+    tpl! { <div><span>x</div> }
+}
+
+#[tplfn]
+fn unmatched() {
+    tpl! { <p>x</p></div> }
+}
+
+#[tplfn]
+fn void_closed() {
+    tpl! { <br></br> }
+}
+
+#[tplfn]
+fn open_at_end() {
+    tpl! { <main> }
+}
+
+#[tplfn]
+fn block_in_paragraph() {
+    tpl! { <p>x<div>y</div></p> }
+}
+
+#[tplfn]
+fn unclosed_in_paragraph() {
+    tpl! { <p><span>x<ul><li>y</ul></span> }
+}
+
+#[tplfn]
+fn paragraph_closed_earlier() {
+    tpl! {
+        <section><p>x<div>y</div></section></p>
+        <p>x<div>y</div><p>z</p></p>
+        <p>a<hr></p>
+    }
+}
+
+fn main() {}
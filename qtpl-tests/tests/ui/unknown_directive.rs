@@ -0,0 +1,8 @@
+use qtpl::{tpl, tplfn};
+
+#[tplfn]
+fn typo(id: &str) {
+    tpl! { <p id={!att id}>x</p> }
+}
+
+fn main() {}
@@ -0,0 +1,38 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::io::Write;
+
+// Compares writing escaped text through `write!` and the formatting machinery
+// against `escape_to`, for text with no, few and many escaped characters.
+fn escape(c: &mut Criterion) {
+    let inputs = [
+        (
+            "plain",
+            "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(20),
+        ),
+        (
+            "sparse",
+            "Tom & Jerry <3 each other, or so they say. ".repeat(20),
+        ),
+        ("dense", r#"<a href="/x">'&'</a>"#.repeat(50)),
+    ];
+    let mut group = c.benchmark_group("escape");
+    for (name, input) in &inputs {
+        let mut w = Vec::with_capacity(input.len() * 6);
+        group.bench_with_input(BenchmarkId::new("write_fmt", name), input, |b, s| {
+            b.iter(|| {
+                w.clear();
+                write!(w, "{}", qtpl::escape(black_box(s))).unwrap();
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("escape_to", name), input, |b, s| {
+            b.iter(|| {
+                w.clear();
+                qtpl::escape_to(&mut w, black_box(s).as_bytes()).unwrap();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, escape);
+criterion_main!(benches);
@@ -0,0 +1,6 @@
+# The default catalog used by the examples in the documentation.
+hello = Hello, { $name }!
+unread = { $count ->
+    [one] One unread email
+   *[other] { $count } unread emails
+}
@@ -0,0 +1,184 @@
+use std::borrow::Cow;
+use std::io::{Error, ErrorKind, Result, Write};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A value that can be written as a named attribute, such as `id={value}`.
+///
+/// Strings are written as an escaped and quoted value. A `bool` writes just
+/// the name when `true`, and an `Option` writes nothing when `None`, which
+/// allows for the attribute to be omitted entirely.
+///
+/// Implement this for your own types to use them as attribute values:
+///
+/// ```
+/// use qtpl::{tplfn, tpl, render_string, AttrValue};
+///
+/// struct Id(u32);
+///
+/// impl AttrValue for Id {
+///     fn write_attr(&self, w: &mut dyn std::io::Write, name: &[u8]) -> std::io::Result<()> {
+///         w.write_all(name)?;
+///         write!(w, "=\"id-{}\"", self.0)
+///     }
+/// }
+///
+/// #[tplfn]
+/// fn item(id: Id) {
+///     tpl! {<li id={id}></li>}
+/// }
+///
+/// assert_eq!(render_string!(item(Id(42))), r#"<li id="id-42"></li>"#);
+/// ```
+pub trait AttrValue {
+    /// Write the attribute. The `name` includes a leading space.
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()>;
+}
+
+impl AttrValue for str {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        w.write_all(name)?;
+        w.write_all(b"=\"")?;
+        crate::escape_to(w, self.as_bytes())?;
+        w.write_all(b"\"")
+    }
+}
+
+impl AttrValue for String {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        self.as_str().write_attr(w, name)
+    }
+}
+
+impl AttrValue for Cow<'_, str> {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        self.as_ref().write_attr(w, name)
+    }
+}
+
+impl AttrValue for Box<str> {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        (**self).write_attr(w, name)
+    }
+}
+
+impl AttrValue for Rc<str> {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        (**self).write_attr(w, name)
+    }
+}
+
+impl AttrValue for Arc<str> {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        (**self).write_attr(w, name)
+    }
+}
+
+impl AttrValue for bool {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        if *self {
+            w.write_all(name)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: AttrValue> AttrValue for Option<T> {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        match self {
+            Some(v) => v.write_attr(w, name),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: AttrValue + ?Sized> AttrValue for &T {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        (**self).write_attr(w, name)
+    }
+}
+
+// This is used internally for named attributes like `id={value}`. A value
+// implementing `AttrValue` is written using it, and any other value which is
+// `AsRef<str>` is written as a string, the same as it was before `AttrValue`.
+// Method resolution prefers the impl for `Attr` over the one for `&Attr`, so
+// calling `(&Attr(&value)).write_attr(..)` picks the first that applies.
+#[doc(hidden)]
+pub struct Attr<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait WriteAttrValue {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()>;
+}
+
+impl<T: AttrValue + ?Sized> WriteAttrValue for Attr<'_, T> {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        self.0.write_attr(w, name)
+    }
+}
+
+#[doc(hidden)]
+pub trait WriteAttrStr {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()>;
+}
+
+impl<T: AsRef<str> + ?Sized> WriteAttrStr for &Attr<'_, T> {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        self.0.as_ref().write_attr(w, name)
+    }
+}
+
+// This is used internally for conditional lists like `class={["a", ("b", c)]}`.
+#[doc(hidden)]
+pub struct Classes<'a>(pub &'a [Option<&'a str>]);
+
+impl AttrValue for Classes<'_> {
+    fn write_attr(&self, w: &mut dyn Write, name: &[u8]) -> Result<()> {
+        let mut classes = self.0.iter().flatten();
+        if let Some(first) = classes.next() {
+            w.write_all(name)?;
+            w.write_all(b"=\"")?;
+            crate::escape_to(w, first.as_bytes())?;
+            for class in classes {
+                w.write_all(b" ")?;
+                crate::escape_to(w, class.as_bytes())?;
+            }
+            w.write_all(b"\"")?;
+        }
+        Ok(())
+    }
+}
+
+// This is used internally for spreading attributes with `{..attrs}`.
+#[doc(hidden)]
+pub fn write_attrs<I, K, V>(w: &mut dyn Write, attrs: I) -> Result<()>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AttrValue,
+{
+    let mut name = vec![];
+    for (k, v) in attrs {
+        let k = k.as_ref();
+        if !is_valid_attr_name(k) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid attribute name: {:?}", k),
+            ));
+        }
+        name.clear();
+        name.push(b' ');
+        name.extend_from_slice(k.as_bytes());
+        v.write_attr(w, &name)?;
+    }
+    Ok(())
+}
+
+// See https://html.spec.whatwg.org/#attribute-name-state for the characters
+// that end an attribute name.
+fn is_valid_attr_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || "\"'<>/=`".contains(c))
+}
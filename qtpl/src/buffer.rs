@@ -0,0 +1,69 @@
+use std::cell::Cell;
+use std::io::{Result, Write};
+
+thread_local! {
+    // The size hint of the template function called first since the last
+    // buffer was created, which is the one the buffer was passed to.
+    static SIZE_HINT: Cell<usize> = const { Cell::new(0) };
+}
+
+// This is called by every template function as it starts, so the buffer of a
+// rendering macro can be allocated for the first one called. Template
+// functions called by it don't override its hint.
+#[doc(hidden)]
+pub fn __size_hint(hint: usize) {
+    SIZE_HINT.with(|h| {
+        if h.get() == 0 {
+            h.set(hint)
+        }
+    })
+}
+
+// The buffer the rendering macros write to. It's allocated on the first write
+// using the size hint of the template function, unless it was given a
+// capacity explicitly.
+#[doc(hidden)]
+pub struct Buffer(Vec<u8>);
+
+impl Buffer {
+    pub fn new() -> Self {
+        SIZE_HINT.with(|h| h.set(0));
+        Self(Vec::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(Vec::with_capacity(capacity))
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+
+    fn reserve(&mut self) {
+        if self.0.capacity() == 0 {
+            self.0.reserve(SIZE_HINT.with(|h| h.replace(0)));
+        }
+    }
+}
+
+impl Default for Buffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.reserve();
+        self.0.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.reserve();
+        self.0.write_all(buf)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
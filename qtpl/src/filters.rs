@@ -0,0 +1,187 @@
+//! Built in filters, used in templates as `{value | filter}`.
+//!
+//! Filters are chained left to right, and the result is escaped according to
+//! where it appears, just like any other block. Each filter takes a reference
+//! to the value, so a value can still be used after being filtered.
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! #
+//! #[tplfn]
+//! fn user(name: &str, nick: Option<&str>, tags: &[&str], count: u32) {
+//!     tpl! {
+//!         <p title={name | upper}>
+//!             {name | trim | capitalize} aka {nick | default("none")},
+//!             {&count.to_string()} tag{count | pluralize}: {tags | join(", ") | truncate(8)}
+//!         </p>
+//!     }
+//! }
+//!
+//! assert_eq!(
+//!     render_string!(user(" bob", None, &["a<b", "c", "d"], 3)),
+//!     r#"<p title=" BOB">Bob aka none, 3 tags: a&lt;b, c,…</p>"#,
+//! );
+//! ```
+//!
+//! `pluralize` writes `s` unless the count is one. It takes an optional plural
+//! suffix like `pluralize("es")`, or both the singular and plural suffixes like
+//! `pluralize("y", "ies")`.
+
+use std::fmt::{Display, Write};
+
+/// Converts the value to uppercase.
+pub fn upper<T: Display + ?Sized>(v: &T) -> String {
+    v.to_string().to_uppercase()
+}
+
+/// Converts the value to lowercase.
+pub fn lower<T: Display + ?Sized>(v: &T) -> String {
+    v.to_string().to_lowercase()
+}
+
+/// Removes whitespace from both ends of the value.
+pub fn trim<T: Display + ?Sized>(v: &T) -> String {
+    v.to_string().trim().to_owned()
+}
+
+/// Uppercases the first character of the value, and lowercases the rest.
+pub fn capitalize<T: Display + ?Sized>(v: &T) -> String {
+    let s = v.to_string();
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => s,
+    }
+}
+
+/// Shortens the value to at most `len` characters, ending with `…` if
+/// anything was removed.
+pub fn truncate<T: Display + ?Sized>(v: &T, len: usize) -> String {
+    let s = v.to_string();
+    match s.char_indices().nth(len) {
+        Some(_) => {
+            let end = s
+                .char_indices()
+                .nth(len.saturating_sub(1))
+                .map_or(0, |(i, _)| i);
+            format!("{}…", s[..end].trim_end())
+        }
+        None => s,
+    }
+}
+
+/// Uses `fallback` when the value is `None`.
+pub fn default<T: Display>(v: &Option<T>, fallback: &str) -> String {
+    match v {
+        Some(v) => v.to_string(),
+        None => fallback.to_owned(),
+    }
+}
+
+/// Joins the items with `sep` in between.
+pub fn join<L: List + ?Sized>(items: &L, sep: &str) -> String {
+    let mut out = String::new();
+    for (i, item) in items.items().iter().enumerate() {
+        if i > 0 {
+            out.push_str(sep);
+        }
+        let _ = write!(out, "{}", item);
+    }
+    out
+}
+
+/// Percent encodes the value for use in a URL, leaving only letters, digits
+/// and `-._~` as they are.
+pub fn urlencode<T: Display + ?Sized>(v: &T) -> String {
+    let mut out = String::new();
+    for b in v.to_string().bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(b as char)
+            }
+            _ => {
+                let _ = write!(out, "%{:02X}", b);
+            }
+        }
+    }
+    out
+}
+
+/// Returns `singular` if the count is one, and `plural` otherwise.
+pub fn pluralize<'a, N: Count + ?Sized>(n: &N, singular: &'a str, plural: &'a str) -> &'a str {
+    if n.is_one() {
+        singular
+    } else {
+        plural
+    }
+}
+
+/// A number that can be used with [`pluralize`](fn.pluralize.html).
+pub trait Count {
+    /// Whether the count is exactly one.
+    fn is_one(&self) -> bool;
+}
+
+macro_rules! impl_count {
+    ($one:literal: $($t:ty)*) => {
+        $(impl Count for $t {
+            fn is_one(&self) -> bool {
+                *self == $one
+            }
+        })*
+    };
+}
+
+impl_count!(1: i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+impl_count!(1.0: f32 f64);
+
+impl<T: Count + ?Sized> Count for &T {
+    fn is_one(&self) -> bool {
+        (**self).is_one()
+    }
+}
+
+/// A list of values that can be used with [`join`](fn.join.html).
+pub trait List {
+    /// The items in the list.
+    fn items(&self) -> Vec<&dyn Display>;
+}
+
+impl<T: Display> List for [T] {
+    fn items(&self) -> Vec<&dyn Display> {
+        self.iter().map(|i| i as &dyn Display).collect()
+    }
+}
+
+impl<T: Display> List for Vec<T> {
+    fn items(&self) -> Vec<&dyn Display> {
+        self.as_slice().items()
+    }
+}
+
+impl<T: Display, const N: usize> List for [T; N] {
+    fn items(&self) -> Vec<&dyn Display> {
+        self.as_slice().items()
+    }
+}
+
+impl<T: List + ?Sized> List for &T {
+    fn items(&self) -> Vec<&dyn Display> {
+        (**self).items()
+    }
+}
+
+impl<T> Count for [T] {
+    fn is_one(&self) -> bool {
+        self.len() == 1
+    }
+}
+
+impl<T> Count for Vec<T> {
+    fn is_one(&self) -> bool {
+        self.len() == 1
+    }
+}
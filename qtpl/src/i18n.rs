@@ -0,0 +1,210 @@
+//! Translated messages, written using the `!i18n` directive.
+//!
+//! A template writes a message by its key, along with the arguments for its
+//! placeholders, as in `{!i18n "hello", name = user.name}`. The message is
+//! looked up in the catalog named `i18n` in scope, which is anything
+//! implementing [`Catalog`](trait.Catalog.html), so it's chosen at runtime,
+//! usually based on the locale of the request:
+//!
+//! ```
+//! use qtpl::i18n::{Message, Messages};
+//! use qtpl::{render_string, tpl, tplfn};
+//!
+//! #[tplfn(i18n_catalog = "locales/en.ftl")]
+//! fn greeting(i18n: &Messages, name: &str) {
+//!     tpl! {<p>{!i18n "hello", name = name}</p>}
+//! }
+//!
+//! let mut de = Messages::new();
+//! de.insert("hello", Message::parse("Hallo, {name}!").unwrap());
+//! assert_eq!(render_string!(greeting(&de, "Bob")), "<p>Hallo, Bob!</p>");
+//! ```
+//!
+//! The default catalog, given by the `i18n_catalog` option or by
+//! `i18n_catalog` in the `[package.metadata.qtpl]` table of `Cargo.toml`, as a
+//! path relative to the crate, is used to check templates at compile time:
+//! the key must exist, and the arguments must match the placeholders of the
+//! message. It's a [Fluent](https://projectfluent.org) file if it ends in
+//! `.ftl`, or a gettext file if it ends in `.po`, which need the `fluent` and
+//! `gettext` features respectively.
+//!
+//! Catalogs are loaded at runtime from the same formats using
+//! `Messages::from_ftl` and `Messages::from_po`, which are enabled by the same
+//! features.
+//!
+//! Both the messages and the arguments are escaped. A message missing from the
+//! catalog is written as its key, and a missing argument as its placeholder.
+//!
+//! ## Plural Messages
+//! A message may have a form for each plural category of one of its
+//! arguments, using a Fluent selector on the
+//! [`Plural`](../locale/enum.Plural.html) categories. Such messages are written
+//! with `!plural`, which picks the form in the locale named `locale` in scope,
+//! and formats the argument for it:
+//!
+//! ```
+//! use qtpl::i18n::{Message, Messages};
+//! use qtpl::locale::{Locale, Plural};
+//! use qtpl::{render_string, tpl, tplfn};
+//!
+//! // unread = { $count ->
+//! //     [one] One unread email
+//! //    *[other] { $count } unread emails
+//! // }
+//! #[tplfn(i18n_catalog = "locales/en.ftl")]
+//! fn inbox(i18n: &Messages, locale: Locale, unread: u32) {
+//!     tpl! {<p>{!plural "unread", count = unread}</p>}
+//! }
+//!
+//! let forms = [
+//!     (Plural::One, "{count} ungelesene E-Mail"),
+//!     (Plural::Other, "{count} ungelesene E-Mails"),
+//! ];
+//! let mut de = Messages::new();
+//! de.insert("unread", Message::plural("count", &forms).unwrap());
+//! let locale = Locale::new("de").unwrap();
+//! assert_eq!(
+//!     render_string!(inbox(&de, locale, 1200)),
+//!     "<p>1.200 ungelesene E-Mails</p>",
+//! );
+//! ```
+//!
+//! gettext catalogs don't support plural forms, so plural messages need a
+//! Fluent catalog.
+
+use crate::escape_to;
+use crate::locale::Locale;
+use std::fmt::Display;
+use std::io::{Result, Write};
+
+pub use qtpl_catalog::{Error, Message, Messages, Part};
+
+/// Messages to write by their key.
+///
+/// This is implemented by [`Messages`](struct.Messages.html), and can be
+/// implemented to use another source, such as a translation service.
+pub trait Catalog {
+    /// Writes the message with the key, replacing its placeholders with the
+    /// arguments by name.
+    fn write_message(
+        &self,
+        w: &mut dyn Write,
+        key: &str,
+        args: &[(&str, &dyn Display)],
+    ) -> Result<()>;
+
+    /// Writes the message with the key like
+    /// [`write_message`](#tymethod.write_message), in the form for the plural
+    /// category in the locale of the argument it selects on. By default the
+    /// message is written as is.
+    fn write_plural(
+        &self,
+        w: &mut dyn Write,
+        key: &str,
+        locale: &Locale,
+        args: &[(&str, &dyn Display)],
+    ) -> Result<()> {
+        let _ = locale;
+        self.write_message(w, key, args)
+    }
+}
+
+impl Catalog for Messages {
+    fn write_message(
+        &self,
+        w: &mut dyn Write,
+        key: &str,
+        args: &[(&str, &dyn Display)],
+    ) -> Result<()> {
+        match self.get(key) {
+            Some(message) => write_message(w, message, args),
+            None => escape_to(w, key.as_bytes()),
+        }
+    }
+
+    fn write_plural(
+        &self,
+        w: &mut dyn Write,
+        key: &str,
+        locale: &Locale,
+        args: &[(&str, &dyn Display)],
+    ) -> Result<()> {
+        match self.get(key) {
+            Some(message) => write_plural(w, message, locale, args),
+            None => escape_to(w, key.as_bytes()),
+        }
+    }
+}
+
+impl<T: Catalog + ?Sized> Catalog for &T {
+    fn write_message(
+        &self,
+        w: &mut dyn Write,
+        key: &str,
+        args: &[(&str, &dyn Display)],
+    ) -> Result<()> {
+        (**self).write_message(w, key, args)
+    }
+
+    fn write_plural(
+        &self,
+        w: &mut dyn Write,
+        key: &str,
+        locale: &Locale,
+        args: &[(&str, &dyn Display)],
+    ) -> Result<()> {
+        (**self).write_plural(w, key, locale, args)
+    }
+}
+
+/// Writes the message escaped, replacing its placeholders with the arguments
+/// by name. This is useful when implementing your own
+/// [`Catalog`](trait.Catalog.html).
+pub fn write_message(
+    w: &mut dyn Write,
+    message: &Message,
+    args: &[(&str, &dyn Display)],
+) -> Result<()> {
+    write_parts(w, message.parts(), args)
+}
+
+/// Writes the form of the message for the plural category of its selector in
+/// the locale, like [`write_message`](fn.write_message.html). The selector is
+/// written formatted for the locale, as by `!number`. Messages without plural
+/// forms are written as they are.
+pub fn write_plural(
+    w: &mut dyn Write,
+    message: &Message,
+    locale: &Locale,
+    args: &[(&str, &dyn Display)],
+) -> Result<()> {
+    let selector = message
+        .selector()
+        .and_then(|s| args.iter().position(|(n, _)| *n == s));
+    let i = match selector {
+        Some(i) => i,
+        None => return write_message(w, message, args),
+    };
+    let (name, value) = args[i];
+    let number = locale.format_number(value);
+    let mut args = args.to_vec();
+    args[i] = (name, &number);
+    write_parts(w, message.form(locale.plural(value)), &args)
+}
+
+fn write_parts(w: &mut dyn Write, parts: &[Part], args: &[(&str, &dyn Display)]) -> Result<()> {
+    for part in parts {
+        match part {
+            Part::Text(text) => escape_to(w, text.as_bytes())?,
+            Part::Placeholder(name) => match args.iter().find(|(n, _)| n == name) {
+                Some((_, value)) => escape_to(w, value.to_string().as_bytes())?,
+                None => {
+                    w.write_all(b"{")?;
+                    escape_to(w, name.as_bytes())?;
+                    w.write_all(b"}")?;
+                }
+            },
+        }
+    }
+    Ok(())
+}
@@ -9,8 +9,13 @@
 //! 1. [Basics](#basics)
 //! 1. [Rendering](#rendering)
 //! 1. [Escaping](#escaping)
+//! 1. [Attributes](#attributes)
+//! 1. [Comments](#comments)
+//! 1. [Directives](#directives)
+//! 1. [Passing Children](#passing-children)
 //! 1. [Returning Errors](#returning-errors)
 //! 1. [Whitespace](#whitespace)
+//! 1. [Well-formed HTML](#well-formed-html)
 //!
 //! # Basics
 //!
@@ -88,6 +93,87 @@
 //! # Ok::<(), std::io::Error>(())
 //! ```
 //!
+//! ## To a `String`
+//! `try_render_string!` is the fallible version, which never panics. It
+//! returns the error of the template function, or an `InvalidData` error
+//! converted into it if the output isn't UTF-8.
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, try_render_string};
+//! #
+//! # #[tplfn]
+//! # fn hello(name: &str) {
+//! #     tpl! {Hello, <strong>{name}</strong>!}
+//! # }
+//! #
+//! let page: String = try_render_string!(hello("world"))?;
+//! assert_eq!(page, "Hello, <strong>world</strong>!");
+//! #
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! ## Methods and Closures
+//! `tplfn` also works for methods, adding the writer after `self`. Along with
+//! function calls, the rendering macros accept method calls, adding the writer
+//! as the first argument, and any other callable, like a closure, which is
+//! called with just the writer.
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! #
+//! struct Page {
+//!     title: String,
+//! }
+//!
+//! impl Page {
+//!     #[tplfn]
+//!     fn header(&self) {
+//!         tpl! {<h1>{self.title}</h1>}
+//!     }
+//! }
+//!
+//! let page = Page { title: "Home".into() };
+//! assert_eq!(render_string!(page.header()), "<h1>Home</h1>");
+//! assert_eq!(
+//!     render_string!(|w: &mut dyn std::io::Write| page.header(w)),
+//!     "<h1>Home</h1>",
+//! );
+//! ```
+//!
+//! ## Size Hints
+//! Template functions know how many bytes they're expected to write, counting
+//! the static text of their templates plus an estimate for each block. The
+//! rendering macros allocate their buffer for the template function they
+//! render, so they usually don't need to grow it.
+//!
+//! `#[tplfn(size_hint)]` also defines a constant with the hint, named after
+//! the function, like `HELLO_SIZE_HINT` for `hello`. It's useful to reserve
+//! capacity in your own buffers, or to give the rendering macros a hint of
+//! your own, which overrides the function's:
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render};
+//! #
+//! #[tplfn(size_hint)]
+//! fn hello(name: &str) {
+//!     tpl! {Hello, <strong>{name}</strong>!}
+//! }
+//!
+//! assert!(render!(hello("world")).capacity() >= HELLO_SIZE_HINT);
+//! let out = render!(hello("world"), size_hint = 1024);
+//! assert!(out.capacity() >= 1024);
+//! #
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! The constant is defined next to the function, so it isn't available for
+//! methods, and `size_hint` can't be used in trait implementations.
+//!
+//! The estimate for a block is 16 bytes, which can be changed for a template
+//! using the `#![block_size_hint = 64]` option, or for all of them in a crate
+//! using `block_size_hint = 64` in the `[package.metadata.qtpl]` table of its
+//! `Cargo.toml`.
+//!
 //! # Escaping
 //!
 //! The default escaping used by the library is geared towards HTML. Using the
@@ -106,6 +192,199 @@
 //! # Ok::<(), std::io::Error>(())
 //! ```
 //!
+//! # Attributes
+//!
+//! A block used as the value of an attribute writes the attribute based on
+//! the type of the value, as defined by [`AttrValue`](trait.AttrValue.html).
+//! Strings are escaped and quoted, a `bool` makes a boolean attribute which
+//! is present only when `true`, and an `Option` omits the attribute when
+//! `None`. Any other value that is `AsRef<str>` is written as a string. An
+//! array makes a space separated list, such as a list of classes, where
+//! `(name, condition)` pairs are only included if the condition holds:
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! #
+//! #[tplfn]
+//! fn button(title: Option<&str>, active: bool, disabled: bool) {
+//!     tpl! {
+//!         <button
+//!             title={title}
+//!             class={["btn", ("active", active)]}
+//!             disabled={disabled}>
+//!             Go
+//!         </button>
+//!     }
+//! }
+//!
+//! assert_eq!(
+//!     render_string!(button(Some("Go!"), true, true)),
+//!     r#"<button title="Go!" class="btn active" disabled> Go </button>"#,
+//! );
+//! assert_eq!(
+//!     render_string!(button(None, false, false)),
+//!     r#"<button class="btn"> Go </button>"#,
+//! );
+//! ```
+//!
+//! Any number of attributes can be spread onto an element using `{..attrs}`,
+//! where `attrs` is anything that iterates over `(name, value)` pairs. Values
+//! are written the same as above, while names are checked at render time, and
+//! an invalid name results in an error:
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! #
+//! #[tplfn]
+//! fn input(extra: &[(&str, &str)]) {
+//!     tpl! {<input type="text" {..extra.iter().copied()}>}
+//! }
+//!
+//! assert_eq!(
+//!     render_string!(input(&[("name", "q"), ("placeholder", "Search")])),
+//!     r#"<input type="text" name="q" placeholder="Search">"#,
+//! );
+//! ```
+//!
+//! # Comments
+//!
+//! HTML comments are written to the output as they appear in the template.
+//! Comments that are only meant for those reading the template are written as
+//! `{# ... #}`, and are removed at compile time:
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! #
+//! #[tplfn]
+//! fn hello(name: &str) {
+//!     tpl! {
+//!         <!-- greeting -->
+//!         {# the name is escaped like any other block #}
+//!         Hello, {name}!
+//!     }
+//! }
+//!
+//! assert_eq!(
+//!     render_string!(hello("world")),
+//!     "<!-- greeting --> Hello, world!",
+//! );
+//! ```
+//!
+//! Since templates are made of Rust tokens, comments must also be valid
+//! tokens. Notably `//` starts a Rust comment, and quotes must be balanced.
+//!
+//! # Directives
+//!
+//! A block can start with a directive to change how the value is written:
+//!
+//! * `{!a value}` writes an escaped and quoted attribute value.
+//! * `{!b value}` writes bytes as they are, without escaping.
+//! * `{!t f(args)}` renders another template function in place.
+//! * `{!i18n "key", name = value}` writes a translated message, which is
+//!   checked against the default catalog at compile time, and
+//!   `{!plural "key", count = n}` writes one with plural forms. See the
+//!   [i18n](i18n/index.html) module.
+//! * `{!number n}`, `{!currency amount, "EUR"}`, `{!date d}` and
+//!   `{!plural n, one = "# item", other = "# items"}` format values for the
+//!   `locale` in scope. See the [locale](locale/index.html) module.
+//!
+//! Some directives use values which aren't among their arguments, by name from
+//! the scope of the template, usually arguments of the template function:
+//!
+//! * `i18n` is the catalog `!i18n` and `!plural "key"` write messages from.
+//! * `locale` is the [`Locale`](locale/struct.Locale.html) the other locale
+//!   directives format values for. They also take it as their last argument,
+//!   as in `{!number n, locale = ctx.locale}`, which `!plural "key"` doesn't.
+//!
+//! If a name isn't in scope, the error points at the directive using it.
+//!
+//! Like the rendering macros, `!t` adds the writer as the first argument of
+//! method calls too, as in `{!t self.sidebar()}`, and calls any other value,
+//! such as a boxed closure, with just the writer:
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! # use std::io::{Result, Write};
+//! #
+//! #[tplfn]
+//! fn card(body: &dyn Fn(&mut dyn Write) -> Result<()>) {
+//!     tpl! {<div>{!t body}</div>}
+//! }
+//!
+//! let body = |w: &mut dyn Write| w.write_all(b"<p>Hi</p>");
+//! assert_eq!(render_string!(card(&body)), "<div><p>Hi</p></div>");
+//! ```
+//!
+//! Templates chosen at runtime can be stored as a
+//! [`DynTemplate`](struct.DynTemplate.html), which is rendered the same way.
+//!
+//! Values can also be transformed using the built in [filters](filters/index.html),
+//! chained as in `{name | trim | upper}`, before being escaped as usual.
+//!
+//! Directives for your own formatting are defined using
+//! `#![directive(name = "path::to::function")]` in the template, or
+//! `#[tplfn(directive(...))]` for all the templates in a function. The function
+//! is called with the writer and a reference to the value. The same function
+//! can also be used as the last filter in a chain with `{value | name}`, or by
+//! its path without defining a directive as in `{value | path::to::function}`.
+//! Any arguments, as in `{value | name(x)}`, are passed after the value. Wrap
+//! the expression in parens for a bitwise or, as in `{(a | b)}`; a single name
+//! which isn't a filter or a directive is an error, since `{a | b}` is
+//! ambiguous.
+//!
+//! Since the function writes directly to the output, it is responsible for
+//! escaping:
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! #
+//! mod fmt {
+//!     use std::io::{Result, Write};
+//!
+//!     pub fn money(w: &mut dyn Write, cents: &u64) -> Result<()> {
+//!         write!(w, "${}.{:02}", cents / 100, cents % 100)
+//!     }
+//! }
+//!
+//! #[tplfn(directive(money = "fmt::money"))]
+//! fn price(cents: u64) {
+//!     tpl! {<p>{!money cents} or {cents | money}</p>}
+//! }
+//!
+//! assert_eq!(render_string!(price(1205)), "<p>$12.05 or $12.05</p>");
+//! ```
+//!
+//! # Passing Children
+//!
+//! A template function can take the content it wraps as a function of the
+//! writer, and render it using `!t`. The `tpl_closure!` macro writes such a
+//! function inline, as a closure which captures variables like any other, so
+//! the content is written directly to the output without being rendered to a
+//! buffer first:
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, tpl_closure, render_string};
+//! # use std::io::{Result, Write};
+//! #
+//! #[tplfn]
+//! fn card(title: &str, body: impl Fn(&mut dyn Write) -> Result<()>) {
+//!     tpl! {<div><h2>{title}</h2>{!t body}</div>}
+//! }
+//!
+//! #[tplfn]
+//! fn profile(name: &str) {
+//!     tpl! {{!t card("Profile", tpl_closure!(|w| {<p>Hello, {name}!</p>}))}}
+//! }
+//!
+//! assert_eq!(
+//!     render_string!(profile("Bob")),
+//!     "<div><h2>Profile</h2><p>Hello, Bob!</p></div>",
+//! );
+//! ```
+//!
+//! Use `tpl_closure!(move |w| {...})` for closures which outlive the variables
+//! they use, for example when creating a [`DynTemplate`](struct.DynTemplate.html).
+//!
 //! # Returning Errors
 //!
 //! The `#[tplfn]` attribute will add a return type of `std::io::Result<()>`,
@@ -148,6 +427,8 @@
 //! * Whitespace around a whitelisted set of elements, where it should be
 //!   insignificant is stripped.
 //! * All whitespace, including newlines is collapsed into a single space.
+//! * The contents of `<pre>`, `<textarea>`, `<script>` and `<style>` are kept
+//!   exactly as written, including newlines and indentation.
 //! * Rules only apply to template text, contents of varibles are not modified.
 //!
 //! This example shows all the rules in action, including how certain tags
@@ -182,11 +463,194 @@
 //! Note how the space inside and around the `<i>` tag is preserved, but the
 //! space around the `<div>` tag is stripped. Also notice how the multiple
 //! spaces inside the `<i>` are collapsed into a single space.
+//!
+//! These rules make up the default `collapse` policy. The policy can be chosen
+//! per template, using either `#![whitespace = "..."]` at the start of the
+//! template, or `#[tplfn(whitespace = "...")]` for all the templates in a
+//! function:
+//!
+//! * `collapse` applies the rules above.
+//! * `preserve` keeps all whitespace inside the template as written.
+//! * `minify` strips the whitespace around all elements, not just the
+//!   whitelisted ones, and collapses the rest.
+//! * `pretty` is like `collapse`, but puts a newline and indentation based on
+//!   the nesting of elements where whitespace is stripped. This is useful for
+//!   debugging and readable snapshot tests, and can be made the default for
+//!   all templates using the `pretty` feature of this crate. Like all
+//!   features, it applies to every crate using qtpl in the build once any of
+//!   them enables it, so libraries shouldn't enable it. Setting `whitespace`
+//!   in `[package.metadata.qtpl]` only applies to the one crate.
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! #
+//! #[tplfn(whitespace = "minify")]
+//! fn list() {
+//!     tpl! {
+//!         <p> <a>One</a> <a>Two</a> </p>
+//!     }
+//! }
+//!
+//! assert_eq!(render_string!(list()), "<p><a>One</a><a>Two</a></p>");
+//! ```
+//!
+//! Going further, `#![minify]` or `#[tplfn(minify)]` uses the `minify`
+//! policy and also shrinks the rest of the static parts of the template, all at
+//! compile time. Closing tags that HTML doesn't require, like the `</li>`
+//! before another `<li>`, and quotes around attribute values that don't need
+//! them are dropped. HTML comments are stripped, inline styles and the
+//! contents of `<style>` are minified, and blank lines are removed from
+//! `<script>`. This can be made the default for all templates using the
+//! `minify` feature of this crate, which also applies to every crate using
+//! qtpl in the build. A template that gives another `whitespace` policy isn't
+//! minified, and when both the `minify` and `pretty` features are enabled,
+//! `pretty` is used.
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! #
+//! #[tplfn(minify)]
+//! fn list() {
+//!     tpl! {
+//!         <!-- the list -->
+//!         <ul class="list">
+//!             <li>One</li>
+//!             <li>Two</li>
+//!         </ul>
+//!     }
+//! }
+//!
+//! assert_eq!(render_string!(list()), "<ul class=list><li>One<li>Two</ul>");
+//! ```
+//!
+//! The whitelist can be extended, for example with custom elements, using
+//! `whitespace_insensitive = "my-card my-row"` in the same places. Defaults
+//! for a crate can be set in the `[package.metadata.qtpl]` table of its
+//! `Cargo.toml`, which only applies to the templates of that crate:
+//!
+//! ```toml
+//! [package.metadata.qtpl]
+//! whitespace = "minify"
+//! whitespace_insensitive = "my-card my-row"
+//! ```
+//!
+//! The rules can be overridden at specific points using whitespace
+//! directives. All of them remove the whitespace around them, and are never
+//! removed themselves:
+//!
+//! * `{-}` leaves no whitespace.
+//! * `{~}` leaves a single space.
+//! * `{~n}` leaves a single newline.
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! #
+//! #[tplfn]
+//! fn nav() {
+//!     tpl! {
+//!         <nav>
+//!             <a>Home</a> {-} | {-} <a>About</a>
+//!             <p>One</p>{~}<p>Two</p>
+//!         </nav>
+//!     }
+//! }
+//!
+//! assert_eq!(
+//!     render_string!(nav()),
+//!     "<nav><a>Home</a>|<a>About</a><p>One</p> <p>Two</p></nav>",
+//! );
+//! ```
+//!
+//! Whitespace is recovered from the source positions of the template tokens,
+//! which the compiler provides starting with Rust 1.88. When positions aren't
+//! available, such as on older compilers, in some IDEs, or when the template
+//! was itself generated by a macro, a single space is placed between words and
+//! after punctuation like `,` or `!`, and nowhere else.
+//!
+//! # Well-formed HTML
+//!
+//! Tags are checked at compile time. An element that is never closed, a
+//! closing tag that doesn't match the open element, or a closing tag for a void
+//! element like `<br>` are all errors. Elements whose closing tag is optional
+//! in HTML, such as `<li>` or `<p>`, may be left open.
+//!
+//! A block like `<div>` or `<ul>` closes an open `<p>`, as browsers do, so a
+//! `</p>` after it is an error. This is the only part of the content model of
+//! HTML that is checked, and other misplaced elements, such as a `<tr>` outside
+//! of a table, are accepted as written.
+//!
+//! Templates that are intentionally partial, for example a header which opens
+//! elements that a footer closes, can opt out using `#![fragment]` at the
+//! start of the template:
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! #
+//! #[tplfn]
+//! fn header() {
+//!     tpl! {
+//!         #![fragment]
+//!         <main>
+//!     }
+//! }
+//!
+//! assert_eq!(render_string!(header()), "<main>");
+//! ```
+//!
+//! Additionally `#![lint]` checks element and attribute names against the
+//! known HTML elements and attributes, catching typos like `<dvi>` or `clas`.
+//! Custom elements, which contain a dash, `data-*` and `aria-*` attributes,
+//! and the contents of `<svg>` and `<math>` are always allowed:
+//!
+//! ```
+//! # use qtpl::{tplfn, tpl, render_string};
+//! #
+//! #[tplfn]
+//! fn card(title: &str) {
+//!     tpl! {
+//!         #![lint]
+//!         <my-card data-id="1" class="card">{title}</my-card>
+//!     }
+//! }
+//!
+//! assert_eq!(
+//!     render_string!(card("hi")),
+//!     r#"<my-card data-id="1" class="card">hi</my-card>"#,
+//! );
+//! ```
 
 #![doc(html_favicon_url = "https://raw.githubusercontent.com/daaku/qtpl/master/assets/favicon.png")]
 #![doc(html_logo_url = "https://raw.githubusercontent.com/daaku/qtpl/master/assets/logo.png")]
 
-pub use qtpl_macros::{render, render_string, tpl, tplfn};
+mod attr;
+mod buffer;
+mod escape;
+pub mod filters;
+pub mod i18n;
+pub mod locale;
+mod template;
+
+pub use attr::AttrValue;
+pub use escape::escape_to;
+pub use qtpl_macros::{render, render_string, tpl, tpl_closure, tplfn, try_render_string};
+pub use template::DynTemplate;
+
+// These are used internally for attributes in macro output.
+#[doc(hidden)]
+pub use attr::{write_attrs, Attr, Classes, WriteAttrStr, WriteAttrValue};
+
+// These are used internally by the rendering macros and template functions
+// to allocate the output up front.
+#[doc(hidden)]
+pub use buffer::{__size_hint, Buffer};
+
+// This is used internally by `try_render_string!` to check the output is
+// UTF-8.
+#[doc(hidden)]
+pub fn __into_string<E: From<std::io::Error>>(w: Vec<u8>) -> Result<String, E> {
+    String::from_utf8(w)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err).into())
+}
 
 // This is used internally for escaping in macro output.
 #[doc(hidden)]
@@ -0,0 +1,423 @@
+//! Plural forms and formatting of numbers, currencies and dates for a locale.
+//!
+//! A [`Locale`](struct.Locale.html) has the CLDR plural rules and formats for
+//! its language and region, which are compiled into the crate. The supported
+//! locales are `de`, `en`, `en-GB`, `es`, `fr`, `it`, `ja`, `ko`, `nl`, `pl`,
+//! `pt` (Brazil), `pt-PT`, `ru`, `sv`, `tr`, `uk` and `zh`. Other regions use
+//! the formats of their language.
+//!
+//! Templates use the locale named `locale` in scope, or the one given as their
+//! last argument, as in `{!number n, locale = ctx.locale}`, using these
+//! directives:
+//!
+//! * `{!number n}` writes a number, rounded to at most three decimals.
+//! * `{!currency amount, "EUR"}` writes an amount in the currency.
+//! * `{!date d}` and `{!date d, long}` write a [`Date`](struct.Date.html).
+//! * `{!plural n, one = "# item", other = "# items"}` writes the form for the
+//!   plural category of the number, with `#` replaced by the number. The
+//!   `other` form is required, and is used for categories without a form.
+//! * `{!plural "key", count = n}` writes a message from the catalog in the
+//!   form for the plural category of its selector, as described in
+//!   [`i18n`](../i18n/index.html#plural-messages).
+//!
+//! ```
+//! use qtpl::locale::{Date, Locale};
+//! use qtpl::{render_string, tpl, tplfn};
+//!
+//! struct Context {
+//!     locale: Locale,
+//! }
+//!
+//! #[tplfn]
+//! fn order(ctx: &Context, items: u32, total: f64, due: Date) {
+//!     let locale = ctx.locale;
+//!     tpl! {
+//!         <p>
+//!             {!plural items, one = "# item", other = "# items"}
+//!             for {!currency total, "EUR"}, due {!date due, long}.
+//!         </p>
+//!     }
+//! }
+//!
+//! let due = Date::new(2024, 3, 1).unwrap();
+//! let en = Context { locale: Locale::new("en-US").unwrap() };
+//! let de = Context { locale: Locale::new("de").unwrap() };
+//! assert_eq!(
+//!     render_string!(order(&en, 1200, 1234.5, due)),
+//!     "<p>1,200 items for €1,234.50, due March 1, 2024.</p>",
+//! );
+//! assert_eq!(
+//!     render_string!(order(&de, 1, 1234.5, due)),
+//!     "<p>1 item for 1.234,50\u{a0}€, due 1. März 2024.</p>",
+//! );
+//! ```
+//!
+//! The same formatting is available outside of templates through the methods
+//! of `Locale`.
+
+mod data;
+mod plural;
+
+use data::Data;
+use plural::Operands;
+use std::fmt::{self, Display};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub use qtpl_catalog::Plural;
+
+/// The length of a formatted date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateStyle {
+    /// Only numbers, as in `3/1/24`.
+    Short,
+    /// With the name of the month, as in `March 1, 2024`.
+    Long,
+}
+
+/// A calendar date, without a time zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    /// The date, or `None` if it doesn't exist. Months and days start from 1.
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        if day == 0 || day > days {
+            return None;
+        }
+        Some(Self { year, month, day })
+    }
+
+    /// The year, where 0 is 1 BC.
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    /// The month, from 1 to 12.
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    /// The day of the month, from 1.
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+}
+
+/// The date of the time in UTC.
+impl From<SystemTime> for Date {
+    fn from(t: SystemTime) -> Self {
+        let secs = match t.duration_since(UNIX_EPOCH) {
+            Ok(d) => d.as_secs() as i64,
+            Err(err) => {
+                let d = err.duration();
+                -(d.as_secs() as i64) - if d.subsec_nanos() > 0 { 1 } else { 0 }
+            }
+        };
+        // the civil date from the days since the epoch, counted in eras of
+        // 400 years starting on March 1st, so leap days end the year.
+        let days = secs.div_euclid(86_400) + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        Self { year, month, day }
+    }
+}
+
+/// The rules and formats of a language, and optionally its region.
+#[derive(Clone, Copy)]
+pub struct Locale {
+    data: &'static Data,
+}
+
+impl Locale {
+    /// The locale for a tag like `de`, `pt-BR` or `en_GB`, using the formats
+    /// of the language if there are none for the region. Returns `None` for
+    /// languages without data.
+    pub fn new(tag: &str) -> Option<Self> {
+        let mut parts = tag.split(['-', '_']);
+        let language = parts.next()?.to_ascii_lowercase();
+        let region = parts.next().map(str::to_ascii_uppercase);
+        let find = |tag: &str| data::LOCALES.iter().find(|d| d.tag == tag);
+        region
+            .and_then(|r| find(&format!("{}-{}", language, r)))
+            .or_else(|| find(&language))
+            .map(|data| Self { data })
+    }
+
+    /// The tag of the locale whose data is used, as in `pt-PT`.
+    pub fn tag(&self) -> &'static str {
+        self.data.tag
+    }
+
+    /// The plural category of the number, as written by `Display`. As in
+    /// CLDR, visible decimals count, so `1.0` may differ from `1`.
+    pub fn plural(&self, n: impl Display) -> Plural {
+        match Number::parse(&n.to_string()) {
+            Some(n) => n.plural(self.data),
+            None => Plural::Other,
+        }
+    }
+
+    /// Picks the form for the plural category of the number, falling back to
+    /// the `Other` one, and replaces `#` in it with the formatted number.
+    pub fn format_plural(&self, n: impl Display, forms: &[(Plural, &str)]) -> String {
+        let s = n.to_string();
+        let (category, formatted) = match Number::parse(&s) {
+            Some(mut n) => {
+                n.round(3);
+                (n.plural(self.data), n.format(self.data))
+            }
+            None => (Plural::Other, s),
+        };
+        let form = forms
+            .iter()
+            .find(|(c, _)| *c == category)
+            .or_else(|| forms.iter().find(|(c, _)| *c == Plural::Other))
+            .map_or("#", |(_, f)| f);
+        form.replace('#', &formatted)
+    }
+
+    /// Formats the number, as written by `Display`, with the separators of
+    /// the locale, rounded to at most three decimals. Values which aren't
+    /// numbers, like `NaN`, are written as they are.
+    pub fn format_number(&self, n: impl Display) -> String {
+        let s = n.to_string();
+        match Number::parse(&s) {
+            Some(mut n) => {
+                n.round(3);
+                n.format(self.data)
+            }
+            None => s,
+        }
+    }
+
+    /// Formats an amount in the currency given by its ISO 4217 code, as in
+    /// `EUR`, using its symbol in the locale if there is one, or the code.
+    pub fn format_currency(&self, amount: impl Display, currency: &str) -> String {
+        let s = amount.to_string();
+        let mut n = match Number::parse(&s) {
+            Some(n) => n,
+            None => return format!("{} {}", s, currency),
+        };
+        let digits = if data::NO_DECIMALS.contains(&currency) {
+            0
+        } else {
+            2
+        };
+        n.round(digits);
+        n.pad(digits);
+        let negative = n.negative;
+        n.negative = false;
+        let number = n.format(self.data);
+        let symbol = self
+            .data
+            .symbols
+            .iter()
+            .chain(data::SYMBOLS)
+            .find(|(code, _)| *code == currency)
+            .map_or(currency, |(_, s)| s);
+        let pattern = if negative {
+            self.data.currency.1
+        } else {
+            self.data.currency.0
+        };
+        let mut out = String::new();
+        let mut last = None;
+        for c in pattern.chars() {
+            match c {
+                '¤' => {
+                    // a symbol of letters next to the number is spaced out.
+                    if last == Some('#') && symbol.starts_with(char::is_alphabetic) {
+                        out.push('\u{a0}');
+                    }
+                    out.push_str(symbol);
+                }
+                '#' => {
+                    if last == Some('¤') && symbol.ends_with(char::is_alphabetic) {
+                        out.push('\u{a0}');
+                    }
+                    out.push_str(&number);
+                }
+                '-' => out.push_str(self.data.minus),
+                c => out.push(c),
+            }
+            last = Some(c);
+        }
+        out
+    }
+
+    /// Formats the date in the style.
+    pub fn format_date(&self, date: impl Into<Date>, style: DateStyle) -> String {
+        let date = date.into();
+        let pattern = match style {
+            DateStyle::Short => self.data.short_date,
+            DateStyle::Long => self.data.long_date,
+        };
+        let mut out = String::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            let mut len = 1;
+            while chars.peek() == Some(&c) && c != '\'' {
+                chars.next();
+                len += 1;
+            }
+            match (c, len) {
+                ('y', 2) => out.push_str(&format!("{:02}", date.year.rem_euclid(100))),
+                ('y', _) => out.push_str(&date.year.to_string()),
+                ('M', 1) | ('d', 1) | ('M', 2) | ('d', 2) => {
+                    let n = if c == 'M' { date.month } else { date.day };
+                    out.push_str(&format!("{:01$}", n, len));
+                }
+                ('M', _) => out.push_str(self.data.months[date.month as usize - 1]),
+                ('\'', _) => out.extend(chars.by_ref().take_while(|c| *c != '\'')),
+                (c, len) => out.extend(std::iter::repeat_n(c, len)),
+            }
+        }
+        out
+    }
+}
+
+/// The English locale, as used in the United States.
+impl Default for Locale {
+    fn default() -> Self {
+        Self::new("en").unwrap()
+    }
+}
+
+impl PartialEq for Locale {
+    fn eq(&self, other: &Self) -> bool {
+        self.tag() == other.tag()
+    }
+}
+
+impl Eq for Locale {}
+
+impl fmt::Debug for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Locale").field(&self.tag()).finish()
+    }
+}
+
+// A decimal number as written, which keeps the digits exactly.
+struct Number {
+    negative: bool,
+    int: String,
+    frac: String,
+}
+
+impl Number {
+    fn parse(s: &str) -> Option<Self> {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int, frac) = match s.find('.') {
+            Some(at) => (&s[..at], &s[at + 1..]),
+            None => (s, ""),
+        };
+        let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if (int.is_empty() && frac.is_empty()) || !digits(int) || !digits(frac) {
+            return None;
+        }
+        let int = int.trim_start_matches('0');
+        Some(Self {
+            negative,
+            int: if int.is_empty() { "0" } else { int }.to_owned(),
+            frac: frac.to_owned(),
+        })
+    }
+
+    fn plural(&self, data: &Data) -> Plural {
+        (data.plural)(&Operands::new(&self.int, &self.frac))
+    }
+
+    // Rounds to at most the number of decimals, half to even, and removes
+    // trailing zeros.
+    fn round(&mut self, decimals: usize) {
+        if self.frac.len() > decimals {
+            let rest = self.frac.split_off(decimals);
+            let last = self
+                .frac
+                .bytes()
+                .last()
+                .or_else(|| self.int.bytes().last())
+                .unwrap_or(b'0');
+            let up = match rest.as_bytes()[0] {
+                b'6'..=b'9' => true,
+                b'5' => rest[1..].bytes().any(|b| b != b'0') || (last - b'0') % 2 == 1,
+                _ => false,
+            };
+            if up {
+                self.increment();
+            }
+        }
+        let len = self.frac.trim_end_matches('0').len();
+        self.frac.truncate(len);
+    }
+
+    // Adds one to the last digit.
+    fn increment(&mut self) {
+        let mut digits: Vec<u8> = format!("{}{}", self.int, self.frac).into_bytes();
+        let mut carry = true;
+        for d in digits.iter_mut().rev() {
+            if *d == b'9' {
+                *d = b'0';
+            } else {
+                *d += 1;
+                carry = false;
+                break;
+            }
+        }
+        if carry {
+            digits.insert(0, b'1');
+        }
+        let frac = digits.split_off(digits.len() - self.frac.len());
+        self.int = String::from_utf8(digits).unwrap_or_default();
+        self.frac = String::from_utf8(frac).unwrap_or_default();
+    }
+
+    // Pads the decimals with zeros to the number of digits.
+    fn pad(&mut self, decimals: usize) {
+        while self.frac.len() < decimals {
+            self.frac.push('0');
+        }
+    }
+
+    fn format(&self, data: &Data) -> String {
+        let mut out = String::new();
+        if self.negative && self.int.bytes().chain(self.frac.bytes()).any(|b| b != b'0') {
+            out.push_str(data.minus);
+        }
+        let grouped = self.int.len() >= 4 + data.min_grouping - 1;
+        for (i, c) in self.int.chars().enumerate() {
+            let left = self.int.len() - i;
+            if grouped && i > 0 && left.is_multiple_of(3) {
+                out.push_str(data.group);
+            }
+            out.push(c);
+        }
+        if !self.frac.is_empty() {
+            out.push_str(data.decimal);
+            out.push_str(&self.frac);
+        }
+        out
+    }
+}
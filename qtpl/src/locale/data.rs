@@ -0,0 +1,412 @@
+// Locale data from CLDR 44, for the locales with formats. Patterns for
+// currencies put the symbol at `¤` and the number at `#`, and dates use the
+// CLDR fields `y`, `yy`, `M`, `MM`, `MMMM`, `d` and `dd` with quoted literals.
+
+use super::plural::{self, Rule};
+
+pub struct Data {
+    pub tag: &'static str,
+    pub plural: Rule,
+    pub decimal: &'static str,
+    pub group: &'static str,
+    // the number of integer digits needed before the first group is used.
+    pub min_grouping: usize,
+    pub minus: &'static str,
+    // the patterns for positive and negative amounts.
+    pub currency: (&'static str, &'static str),
+    // the symbols of currencies used in the locale, other than the euro and
+    // the pound, which are the same everywhere.
+    pub symbols: &'static [(&'static str, &'static str)],
+    pub short_date: &'static str,
+    pub long_date: &'static str,
+    // the names of the months as used in the long date.
+    pub months: [&'static str; 12],
+}
+
+const NBSP: &str = "\u{a0}";
+const NNBSP: &str = "\u{202f}";
+
+const EN_MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const PT_MONTHS: [&str; 12] = [
+    "janeiro",
+    "fevereiro",
+    "março",
+    "abril",
+    "maio",
+    "junho",
+    "julho",
+    "agosto",
+    "setembro",
+    "outubro",
+    "novembro",
+    "dezembro",
+];
+
+// for the languages where the long date uses the number of the month.
+const NUMBERED_MONTHS: [&str; 12] = [
+    "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12",
+];
+
+pub const LOCALES: &[Data] = &[
+    Data {
+        tag: "de",
+        plural: plural::one_integer,
+        decimal: ",",
+        group: ".",
+        min_grouping: 1,
+        minus: "-",
+        currency: ("#\u{a0}¤", "-#\u{a0}¤"),
+        symbols: &[],
+        short_date: "dd.MM.yy",
+        long_date: "d. MMMM y",
+        months: [
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ],
+    },
+    Data {
+        tag: "en",
+        plural: plural::one_integer,
+        decimal: ".",
+        group: ",",
+        min_grouping: 1,
+        minus: "-",
+        currency: ("¤#", "-¤#"),
+        symbols: &[("USD", "$")],
+        short_date: "M/d/yy",
+        long_date: "MMMM d, y",
+        months: EN_MONTHS,
+    },
+    Data {
+        tag: "en-GB",
+        plural: plural::one_integer,
+        decimal: ".",
+        group: ",",
+        min_grouping: 1,
+        minus: "-",
+        currency: ("¤#", "-¤#"),
+        symbols: &[("USD", "US$")],
+        short_date: "dd/MM/y",
+        long_date: "d MMMM y",
+        months: EN_MONTHS,
+    },
+    Data {
+        tag: "es",
+        plural: plural::spanish,
+        decimal: ",",
+        group: ".",
+        min_grouping: 2,
+        minus: "-",
+        currency: ("#\u{a0}¤", "-#\u{a0}¤"),
+        symbols: &[],
+        short_date: "d/M/yy",
+        long_date: "d 'de' MMMM 'de' y",
+        months: [
+            "enero",
+            "febrero",
+            "marzo",
+            "abril",
+            "mayo",
+            "junio",
+            "julio",
+            "agosto",
+            "septiembre",
+            "octubre",
+            "noviembre",
+            "diciembre",
+        ],
+    },
+    Data {
+        tag: "fr",
+        plural: plural::french,
+        decimal: ",",
+        group: NNBSP,
+        min_grouping: 1,
+        minus: "-",
+        currency: ("#\u{a0}¤", "-#\u{a0}¤"),
+        symbols: &[],
+        short_date: "dd/MM/y",
+        long_date: "d MMMM y",
+        months: [
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+    },
+    Data {
+        tag: "it",
+        plural: plural::italian,
+        decimal: ",",
+        group: ".",
+        min_grouping: 1,
+        minus: "-",
+        currency: ("#\u{a0}¤", "-#\u{a0}¤"),
+        symbols: &[],
+        short_date: "dd/MM/yy",
+        long_date: "d MMMM y",
+        months: [
+            "gennaio",
+            "febbraio",
+            "marzo",
+            "aprile",
+            "maggio",
+            "giugno",
+            "luglio",
+            "agosto",
+            "settembre",
+            "ottobre",
+            "novembre",
+            "dicembre",
+        ],
+    },
+    Data {
+        tag: "ja",
+        plural: plural::none,
+        decimal: ".",
+        group: ",",
+        min_grouping: 1,
+        minus: "-",
+        currency: ("¤#", "-¤#"),
+        symbols: &[("JPY", "￥")],
+        short_date: "y/MM/dd",
+        long_date: "y年M月d日",
+        months: NUMBERED_MONTHS,
+    },
+    Data {
+        tag: "ko",
+        plural: plural::none,
+        decimal: ".",
+        group: ",",
+        min_grouping: 1,
+        minus: "-",
+        currency: ("¤#", "-¤#"),
+        symbols: &[("KRW", "₩")],
+        short_date: "yy. M. d.",
+        long_date: "y년 M월 d일",
+        months: NUMBERED_MONTHS,
+    },
+    Data {
+        tag: "nl",
+        plural: plural::one_integer,
+        decimal: ",",
+        group: ".",
+        min_grouping: 1,
+        minus: "-",
+        currency: ("¤\u{a0}#", "¤\u{a0}-#"),
+        symbols: &[],
+        short_date: "dd-MM-y",
+        long_date: "d MMMM y",
+        months: [
+            "januari",
+            "februari",
+            "maart",
+            "april",
+            "mei",
+            "juni",
+            "juli",
+            "augustus",
+            "september",
+            "oktober",
+            "november",
+            "december",
+        ],
+    },
+    Data {
+        tag: "pl",
+        plural: plural::polish,
+        decimal: ",",
+        group: NBSP,
+        min_grouping: 2,
+        minus: "-",
+        currency: ("#\u{a0}¤", "-#\u{a0}¤"),
+        symbols: &[("PLN", "zł")],
+        short_date: "d.MM.y",
+        long_date: "d MMMM y",
+        months: [
+            "stycznia",
+            "lutego",
+            "marca",
+            "kwietnia",
+            "maja",
+            "czerwca",
+            "lipca",
+            "sierpnia",
+            "września",
+            "października",
+            "listopada",
+            "grudnia",
+        ],
+    },
+    Data {
+        tag: "pt",
+        plural: plural::portuguese,
+        decimal: ",",
+        group: ".",
+        min_grouping: 1,
+        minus: "-",
+        currency: ("¤\u{a0}#", "-¤\u{a0}#"),
+        symbols: &[("BRL", "R$"), ("USD", "US$")],
+        short_date: "dd/MM/y",
+        long_date: "d 'de' MMMM 'de' y",
+        months: PT_MONTHS,
+    },
+    Data {
+        tag: "pt-PT",
+        plural: plural::italian,
+        decimal: ",",
+        group: NBSP,
+        min_grouping: 2,
+        minus: "-",
+        currency: ("#\u{a0}¤", "-#\u{a0}¤"),
+        symbols: &[("USD", "US$")],
+        short_date: "dd/MM/yy",
+        long_date: "d 'de' MMMM 'de' y",
+        months: PT_MONTHS,
+    },
+    Data {
+        tag: "ru",
+        plural: plural::east_slavic,
+        decimal: ",",
+        group: NBSP,
+        min_grouping: 1,
+        minus: "-",
+        currency: ("#\u{a0}¤", "-#\u{a0}¤"),
+        symbols: &[("RUB", "₽"), ("USD", "$")],
+        short_date: "dd.MM.y",
+        long_date: "d MMMM y 'г'.",
+        months: [
+            "января",
+            "февраля",
+            "марта",
+            "апреля",
+            "мая",
+            "июня",
+            "июля",
+            "августа",
+            "сентября",
+            "октября",
+            "ноября",
+            "декабря",
+        ],
+    },
+    Data {
+        tag: "sv",
+        plural: plural::one_integer,
+        decimal: ",",
+        group: NBSP,
+        min_grouping: 1,
+        minus: "\u{2212}",
+        currency: ("#\u{a0}¤", "\u{2212}#\u{a0}¤"),
+        symbols: &[("SEK", "kr"), ("USD", "US$")],
+        short_date: "y-MM-dd",
+        long_date: "d MMMM y",
+        months: [
+            "januari",
+            "februari",
+            "mars",
+            "april",
+            "maj",
+            "juni",
+            "juli",
+            "augusti",
+            "september",
+            "oktober",
+            "november",
+            "december",
+        ],
+    },
+    Data {
+        tag: "tr",
+        plural: plural::one_exact,
+        decimal: ",",
+        group: ".",
+        min_grouping: 1,
+        minus: "-",
+        currency: ("¤#", "-¤#"),
+        symbols: &[("TRY", "₺"), ("USD", "$")],
+        short_date: "d.MM.y",
+        long_date: "d MMMM y",
+        months: [
+            "Ocak", "Şubat", "Mart", "Nisan", "Mayıs", "Haziran", "Temmuz", "Ağustos", "Eylül",
+            "Ekim", "Kasım", "Aralık",
+        ],
+    },
+    Data {
+        tag: "uk",
+        plural: plural::east_slavic,
+        decimal: ",",
+        group: NBSP,
+        min_grouping: 1,
+        minus: "-",
+        currency: ("#\u{a0}¤", "-#\u{a0}¤"),
+        symbols: &[("UAH", "₴")],
+        short_date: "dd.MM.yy",
+        long_date: "d MMMM y 'р'.",
+        months: [
+            "січня",
+            "лютого",
+            "березня",
+            "квітня",
+            "травня",
+            "червня",
+            "липня",
+            "серпня",
+            "вересня",
+            "жовтня",
+            "листопада",
+            "грудня",
+        ],
+    },
+    Data {
+        tag: "zh",
+        plural: plural::none,
+        decimal: ".",
+        group: ",",
+        min_grouping: 1,
+        minus: "-",
+        currency: ("¤#", "-¤#"),
+        symbols: &[("CNY", "¥"), ("USD", "US$")],
+        short_date: "y/M/d",
+        long_date: "y年M月d日",
+        months: NUMBERED_MONTHS,
+    },
+];
+
+// The symbols of the currencies which are the same in all the locales.
+pub const SYMBOLS: &[(&str, &str)] = &[("EUR", "€"), ("GBP", "£")];
+
+// The currencies without minor units, which are written without decimals.
+pub const NO_DECIMALS: &[&str] = &["CLP", "ISK", "JPY", "KRW", "VND"];
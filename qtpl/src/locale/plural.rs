@@ -0,0 +1,131 @@
+// The cardinal plural rules from CLDR 44, for the languages with data. Each
+// takes the operands of the number as written, so `1` and `1.0` may differ.
+
+use super::Plural;
+
+// The operands used by the rules: the integer digits, the number of visible
+// fraction digits, and the fraction digits.
+pub struct Operands {
+    pub i: u64,
+    pub v: usize,
+    pub f: u64,
+}
+
+impl Operands {
+    pub fn new(int: &str, frac: &str) -> Self {
+        let parse = |s: &str| s.parse().unwrap_or(u64::MAX);
+        Self {
+            i: if int.is_empty() { 0 } else { parse(int) },
+            v: frac.len(),
+            f: if frac.is_empty() { 0 } else { parse(frac) },
+        }
+    }
+
+    // Whether the number is exactly the integer.
+    fn n_is(&self, n: u64) -> bool {
+        self.i == n && self.f == 0
+    }
+}
+
+pub type Rule = fn(&Operands) -> Plural;
+
+// ja, ko, zh
+pub fn none(_: &Operands) -> Plural {
+    Plural::Other
+}
+
+// de, en, nl, sv
+pub fn one_integer(o: &Operands) -> Plural {
+    if o.i == 1 && o.v == 0 {
+        Plural::One
+    } else {
+        Plural::Other
+    }
+}
+
+// tr
+pub fn one_exact(o: &Operands) -> Plural {
+    if o.n_is(1) {
+        Plural::One
+    } else {
+        Plural::Other
+    }
+}
+
+// Millions and more, which take a different form in the Romance languages,
+// as in "1 million de personnes".
+fn millions(o: &Operands) -> bool {
+    o.i != 0 && o.i.is_multiple_of(1_000_000) && o.v == 0
+}
+
+// es
+pub fn spanish(o: &Operands) -> Plural {
+    if o.n_is(1) {
+        Plural::One
+    } else if millions(o) {
+        Plural::Many
+    } else {
+        Plural::Other
+    }
+}
+
+// fr
+pub fn french(o: &Operands) -> Plural {
+    if o.i <= 1 {
+        Plural::One
+    } else if millions(o) {
+        Plural::Many
+    } else {
+        Plural::Other
+    }
+}
+
+// it, pt-PT
+pub fn italian(o: &Operands) -> Plural {
+    if o.i == 1 && o.v == 0 {
+        Plural::One
+    } else if millions(o) {
+        Plural::Many
+    } else {
+        Plural::Other
+    }
+}
+
+// pt
+pub fn portuguese(o: &Operands) -> Plural {
+    if o.i <= 1 {
+        Plural::One
+    } else if millions(o) {
+        Plural::Many
+    } else {
+        Plural::Other
+    }
+}
+
+// pl
+pub fn polish(o: &Operands) -> Plural {
+    let (i10, i100) = (o.i % 10, o.i % 100);
+    if o.v != 0 {
+        Plural::Other
+    } else if o.i == 1 {
+        Plural::One
+    } else if (2..=4).contains(&i10) && !(12..=14).contains(&i100) {
+        Plural::Few
+    } else {
+        Plural::Many
+    }
+}
+
+// ru, uk
+pub fn east_slavic(o: &Operands) -> Plural {
+    let (i10, i100) = (o.i % 10, o.i % 100);
+    if o.v != 0 {
+        Plural::Other
+    } else if i10 == 1 && i100 != 11 {
+        Plural::One
+    } else if (2..=4).contains(&i10) && !(12..=14).contains(&i100) {
+        Plural::Few
+    } else {
+        Plural::Many
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+use std::io::{Error, Write};
+use std::ops::Deref;
+
+/// A template chosen at runtime, such as a theme or a widget provided by a
+/// plugin, which can be stored in collections.
+///
+/// It wraps any function that takes the writer, including template functions
+/// without arguments, and closures which call template functions with theirs.
+/// It derefs to the function, so it's rendered in a template using `!t` like
+/// other templates:
+///
+/// ```
+/// use qtpl::{tplfn, tpl, render_string, DynTemplate};
+/// use std::collections::HashMap;
+///
+/// #[tplfn]
+/// fn plain() {
+///     tpl! {<p>Welcome</p>}
+/// }
+///
+/// #[tplfn]
+/// fn fancy(name: &str) {
+///     tpl! {<h1>Welcome to {name}</h1>}
+/// }
+///
+/// #[tplfn]
+/// fn page(header: &DynTemplate) {
+///     tpl! {<main>{!t header}</main>}
+/// }
+///
+/// let mut themes = HashMap::new();
+/// themes.insert("plain", DynTemplate::new(plain));
+/// themes.insert("fancy", DynTemplate::new(|w| fancy(w, "Acme")));
+///
+/// assert_eq!(
+///     render_string!(page(&themes["fancy"])),
+///     "<main><h1>Welcome to Acme</h1></main>",
+/// );
+/// ```
+///
+/// Template functions returning their own error type can be wrapped by
+/// specifying it, as in `DynTemplate<'_, MyError>`.
+pub struct DynTemplate<'a, E = Error>(Box<TemplateFn<'a, E>>);
+
+// The function wrapped by a `DynTemplate`.
+type TemplateFn<'a, E> = dyn Fn(&mut dyn Write) -> Result<(), E> + Send + Sync + 'a;
+
+impl<'a, E> DynTemplate<'a, E> {
+    /// Wraps a function that renders a template to the writer.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&mut dyn Write) -> Result<(), E> + Send + Sync + 'a,
+    {
+        DynTemplate(Box::new(f))
+    }
+
+    /// Renders the template to the writer.
+    pub fn render(&self, w: &mut dyn Write) -> Result<(), E> {
+        (self.0)(w)
+    }
+}
+
+impl<'a, E> Deref for DynTemplate<'a, E> {
+    type Target = TemplateFn<'a, E>;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl<E> fmt::Debug for DynTemplate<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("DynTemplate")
+    }
+}